@@ -0,0 +1,64 @@
+from os import PathLike
+from typing import Iterable, Iterator, Literal, Optional, Union
+
+LabelType = Literal["tx", "addr", "pubkey", "input", "output", "xpub", "spscan"]
+LabelDict = dict[str, Union[str, bool, None]]
+Path = Union[str, PathLike[str]]
+
+class ParseError(ValueError): ...
+class ExportError(Exception): ...
+class EncryptionError(Exception): ...
+class WrongPassphraseError(EncryptionError): ...
+
+class LabelParseOptions:
+    ignore_unknown_types: bool
+    def __init__(self, ignore_unknown_types: bool = False) -> None: ...
+
+class Label:
+    @property
+    def type(self) -> LabelType: ...
+    @property
+    def ref(self) -> str: ...
+    @property
+    def label(self) -> Optional[str]: ...
+    @property
+    def origin(self) -> Optional[str]: ...
+    @property
+    def spendable(self) -> Optional[bool]: ...
+    @staticmethod
+    def from_dict(dict: LabelDict) -> Label: ...
+    def to_dict(self) -> LabelDict: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
+class Labels:
+    def __init__(self, labels: Optional[Iterable[Union[Label, LabelDict]]] = None) -> None: ...
+    @staticmethod
+    def try_from_str(jsonl: str, options: Optional[LabelParseOptions] = None) -> Labels: ...
+    @staticmethod
+    def try_from_file(path: Path, options: Optional[LabelParseOptions] = None) -> Labels: ...
+    def export(self) -> str: ...
+    def export_to_file(self, path: Path) -> None: ...
+    def to_dicts(self) -> list[LabelDict]: ...
+    def __len__(self) -> int: ...
+    def __getitem__(self, index: int) -> Label: ...
+    def __iter__(self) -> Iterator[Label]: ...
+    def __eq__(self, other: object) -> bool: ...
+
+class EncryptedLabels:
+    @staticmethod
+    def encrypt(labels: Labels, passphrase: str, work_factor: Optional[int] = None) -> EncryptedLabels: ...
+    @staticmethod
+    def from_hex(hex: str) -> EncryptedLabels: ...
+    @staticmethod
+    def from_armor(armor: str) -> EncryptedLabels: ...
+    @staticmethod
+    def from_bytes(encoded: bytes) -> EncryptedLabels: ...
+    @staticmethod
+    def read_from_file(path: Path) -> EncryptedLabels: ...
+    def decrypt(self, passphrase: str) -> Labels: ...
+    def to_hex(self) -> str: ...
+    def to_armor(self) -> str: ...
+    def write_to_file(self, path: Path) -> None: ...
+    def __bytes__(self) -> bytes: ...
+    def __eq__(self, other: object) -> bool: ...
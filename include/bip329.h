@@ -0,0 +1,240 @@
+#ifndef BIP329_H
+#define BIP329_H
+
+/* Generated with cbindgen from src/capi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a fallible call
+typedef enum Bip329ErrorCode {
+  BIP329_ERROR_CODE_OK = 0,
+  // A required pointer argument was null
+  BIP329_ERROR_CODE_NULL_POINTER,
+  // A string argument was not valid UTF-8, or a returned string would contain a NUL byte
+  BIP329_ERROR_CODE_INVALID_STRING,
+  // The labels are not valid BIP329
+  BIP329_ERROR_CODE_PARSE_ERROR,
+  // The labels file could not be read
+  BIP329_ERROR_CODE_FILE_READ_ERROR,
+  // The labels could not be serialized
+  BIP329_ERROR_CODE_EXPORT_ERROR,
+  // The labels file could not be written
+  BIP329_ERROR_CODE_FILE_WRITE_ERROR,
+  // The labels could not be encrypted or decrypted
+  BIP329_ERROR_CODE_ENCRYPTION_ERROR,
+  // The passphrase can't decrypt the labels
+  BIP329_ERROR_CODE_WRONG_PASSPHRASE,
+  // The record index is past the end of the labels
+  BIP329_ERROR_CODE_INDEX_OUT_OF_RANGE,
+} Bip329ErrorCode;
+
+// The BIP329 `type` of a record
+typedef enum Bip329LabelType {
+  BIP329_LABEL_TYPE_TRANSACTION,
+  BIP329_LABEL_TYPE_ADDRESS,
+  BIP329_LABEL_TYPE_PUBLIC_KEY,
+  BIP329_LABEL_TYPE_INPUT,
+  BIP329_LABEL_TYPE_OUTPUT,
+  BIP329_LABEL_TYPE_EXTENDED_PUBLIC_KEY,
+  BIP329_LABEL_TYPE_SILENT_PAYMENTS_SCAN,
+  // Returned by `bip329_record_type()` for a null record
+  BIP329_LABEL_TYPE_INVALID = -1,
+} Bip329LabelType;
+
+// A list of labels.
+typedef struct Bip329Labels Bip329Labels;
+
+// A copy of a single label record, with its fields as C strings
+typedef struct Bip329Record Bip329Record;
+
+// Options for parsing BIP329 JSONL
+typedef struct Bip329ParseOptions {
+  // Skip records whose `type` is not supported, instead of failing
+  bool ignore_unknown_types;
+} Bip329ParseOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The message of the last failed call on this thread, or null if no call has failed.
+//
+// The string is owned by the library and valid until the next failed call on this
+// thread.
+const char *bip329_last_error_message(void);
+
+// Parse labels from a JSONL string, `options` may be null for the defaults.
+//
+// On success `*out` is set to a handle that must be freed with `bip329_labels_free()`.
+//
+// # Safety
+//
+// `jsonl` must be a NUL-terminated string, `options` null or valid, and `out` valid
+// for writes.
+enum Bip329ErrorCode bip329_labels_parse(const char *jsonl,
+                                         const struct Bip329ParseOptions *options,
+                                         struct Bip329Labels **out);
+
+// Parse labels from a JSONL file, `options` may be null for the defaults.
+//
+// On success `*out` is set to a handle that must be freed with `bip329_labels_free()`.
+//
+// # Safety
+//
+// `path` must be a NUL-terminated string, `options` null or valid, and `out` valid
+// for writes.
+enum Bip329ErrorCode bip329_labels_parse_file(const char *path,
+                                              const struct Bip329ParseOptions *options,
+                                              struct Bip329Labels **out);
+
+// Free labels, does nothing if `labels` is null.
+//
+// # Safety
+//
+// `labels` must be null or a handle from this library that has not been freed.
+void bip329_labels_free(struct Bip329Labels *labels);
+
+// The number of records, or 0 if `labels` is null.
+//
+// # Safety
+//
+// `labels` must be null or a handle from this library that has not been freed.
+size_t bip329_labels_len(const struct Bip329Labels *labels);
+
+// Copy the record at `index`.
+//
+// On success `*out` is set to a record that must be freed with `bip329_record_free()`.
+//
+// # Safety
+//
+// `labels` must be a handle from this library that has not been freed, and `out`
+// valid for writes.
+enum Bip329ErrorCode bip329_labels_get(const struct Bip329Labels *labels,
+                                       size_t index,
+                                       struct Bip329Record **out);
+
+// Export the labels to a JSONL string.
+//
+// On success `*out` is set to a string that must be freed with `bip329_string_free()`.
+//
+// # Safety
+//
+// `labels` must be a handle from this library that has not been freed, and `out`
+// valid for writes.
+enum Bip329ErrorCode bip329_labels_export(const struct Bip329Labels *labels, char **out);
+
+// Export the labels to a JSONL file.
+//
+// # Safety
+//
+// `labels` must be a handle from this library that has not been freed, and `path` a
+// NUL-terminated string.
+enum Bip329ErrorCode bip329_labels_export_to_file(const struct Bip329Labels *labels,
+                                                  const char *path);
+
+// Encrypt the labels with a passphrase, as ASCII armored age.
+//
+// On success `*out` is set to a string that must be freed with `bip329_string_free()`.
+//
+// # Safety
+//
+// `labels` must be a handle from this library that has not been freed, `passphrase`
+// a NUL-terminated string, and `out` valid for writes.
+enum Bip329ErrorCode bip329_labels_encrypt(const struct Bip329Labels *labels,
+                                           const char *passphrase,
+                                           char **out);
+
+// Encrypt the labels with a passphrase, and write them to a binary age file.
+//
+// # Safety
+//
+// `labels` must be a handle from this library that has not been freed, and
+// `passphrase` and `path` NUL-terminated strings.
+enum Bip329ErrorCode bip329_labels_encrypt_to_file(const struct Bip329Labels *labels,
+                                                   const char *passphrase,
+                                                   const char *path);
+
+// Decrypt labels from binary, ASCII armored or hex encoded age bytes.
+//
+// On success `*out` is set to a handle that must be freed with `bip329_labels_free()`.
+//
+// # Safety
+//
+// `data` must be valid for reads of `len` bytes, `passphrase` a NUL-terminated string,
+// and `out` valid for writes.
+enum Bip329ErrorCode bip329_labels_decrypt(const uint8_t *data,
+                                           size_t len,
+                                           const char *passphrase,
+                                           struct Bip329Labels **out);
+
+// Decrypt labels from a binary, ASCII armored or hex encoded age file.
+//
+// On success `*out` is set to a handle that must be freed with `bip329_labels_free()`.
+//
+// # Safety
+//
+// `path` and `passphrase` must be NUL-terminated strings, and `out` valid for writes.
+enum Bip329ErrorCode bip329_labels_decrypt_file(const char *path,
+                                                const char *passphrase,
+                                                struct Bip329Labels **out);
+
+// The BIP329 `type` of the record, or `BIP329_LABEL_TYPE_INVALID` if `record` is null.
+//
+// # Safety
+//
+// `record` must be null or a record from this library that has not been freed.
+enum Bip329LabelType bip329_record_type(const struct Bip329Record *record);
+
+// The BIP329 `ref` of the record, owned by the record, or null if `record` is null.
+//
+// # Safety
+//
+// `record` must be null or a record from this library that has not been freed.
+const char *bip329_record_ref(const struct Bip329Record *record);
+
+// The label of the record, owned by the record, or null if it has no label or
+// `record` is null.
+//
+// # Safety
+//
+// `record` must be null or a record from this library that has not been freed.
+const char *bip329_record_label(const struct Bip329Record *record);
+
+// The origin descriptor of a transaction record, owned by the record, or null.
+//
+// # Safety
+//
+// `record` must be null or a record from this library that has not been freed.
+const char *bip329_record_origin(const struct Bip329Record *record);
+
+// 1 if an output record is spendable, which is the default, 0 if it is not, and -1
+// for other records or if `record` is null.
+//
+// # Safety
+//
+// `record` must be null or a record from this library that has not been freed.
+int bip329_record_spendable(const struct Bip329Record *record);
+
+// Free a record, does nothing if `record` is null.
+//
+// # Safety
+//
+// `record` must be null or a record from this library that has not been freed.
+void bip329_record_free(struct Bip329Record *record);
+
+// Free a string returned by the library, does nothing if `string` is null.
+//
+// # Safety
+//
+// `string` must be null or a string returned by the library that has not been freed.
+void bip329_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BIP329_H */
@@ -0,0 +1,55 @@
+//! The `bip329-server` label sync server, requires the `server-bin` feature.
+//!
+//! Stores the encrypted labels uploaded by each wallet's devices, see the
+//! `bip329::remote` module for the HTTP API and the matching client.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use bip329::remote::server::LabelServer;
+use clap::Parser;
+
+const TOKEN_ENV: &str = "BIP329_SERVER_TOKEN";
+
+/// Serve encrypted BIP329 labels for syncing between devices
+#[derive(Parser)]
+#[command(name = "bip329-server", version)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8329")]
+    listen: String,
+
+    /// Directory the labels are stored in
+    #[arg(long)]
+    data_dir: PathBuf,
+
+    /// Require this access token from clients, `BIP329_SERVER_TOKEN` by default
+    #[arg(long)]
+    token: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let server = match LabelServer::bind(&args.listen, &args.data_dir) {
+        Ok(server) => server,
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let token = args.token.or_else(|| std::env::var(TOKEN_ENV).ok());
+    let server = match token {
+        Some(token) => server.with_token(token),
+        None => server,
+    };
+
+    eprintln!(
+        "serving labels from {} on http://{}",
+        args.data_dir.display(),
+        server.local_addr()
+    );
+
+    server.run();
+    ExitCode::SUCCESS
+}
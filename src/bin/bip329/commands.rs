@@ -0,0 +1,486 @@
+//! The subcommands of the command line tool.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use bip329::{
+    encryption::{self, EncryptedLabels},
+    is_known_label_type, Label, LabelRef, Labels, LABEL_TYPES,
+};
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+use crate::{
+    editor,
+    error::CliError,
+    format::{self, Format},
+    Command,
+};
+
+/// Environment variable read for the passphrase, before prompting
+const PASSPHRASE_ENV: &str = "BIP329_PASSPHRASE";
+
+/// The result of a successful command
+pub enum Output {
+    /// Labels were written to stdout
+    Data,
+    /// Labels were written to stdout without the records the format can't represent,
+    /// a summary for stderr, printed as JSON with `--json`
+    PartialData { json: Value, text: String },
+    /// A summary, printed as JSON with `--json`
+    Report {
+        json: Value,
+        text: String,
+        success: bool,
+    },
+}
+
+impl Output {
+    fn report(json: Value, text: impl Into<String>) -> Self {
+        Self::Report {
+            json,
+            text: text.into(),
+            success: true,
+        }
+    }
+}
+
+pub fn run(command: Command) -> Result<Output, CliError> {
+    match command {
+        Command::Validate { files, format } => validate(&files, format),
+        Command::Stats { file, format } => stats(&file, format),
+        Command::Convert {
+            input,
+            from,
+            to,
+            output,
+        } => convert(&input, from, to, output.as_deref()),
+        Command::Merge { files, output } => merge(&files, output.as_deref()),
+        Command::Diff { old, new } => diff(&old, &new),
+        Command::Filter {
+            file,
+            types,
+            search,
+            unlabeled,
+            unspendable,
+            output,
+        } => {
+            let filter = Filter::new(types, search, unlabeled, unspendable)?;
+            filter_labels(&file, &filter, output.as_deref())
+        }
+        Command::Edit { file, format } => edit(&file, format),
+        Command::Encrypt {
+            input,
+            output,
+            recipients,
+            armor,
+            passphrase_file,
+        } => encrypt(
+            &input,
+            output.as_deref(),
+            &recipients,
+            armor,
+            passphrase_file.as_deref(),
+        ),
+        Command::Decrypt {
+            input,
+            output,
+            identity,
+            passphrase_file,
+        } => decrypt(
+            &input,
+            output.as_deref(),
+            identity.as_deref(),
+            passphrase_file.as_deref(),
+        ),
+    }
+}
+
+fn read(path: &Path, format: Option<Format>) -> Result<Labels, CliError> {
+    format::read(path, Format::resolve(format, path)?)
+}
+
+fn validate(files: &[PathBuf], format: Option<Format>) -> Result<Output, CliError> {
+    let mut results = Vec::new();
+    let mut text = Vec::new();
+    let mut success = true;
+
+    for path in files {
+        match read(path, format) {
+            Ok(labels) => {
+                results.push(json!({
+                    "file": path,
+                    "valid": true,
+                    "records": labels.len(),
+                }));
+                text.push(format!("{}: ok, {} records", path.display(), labels.len()));
+            }
+            Err(error @ (CliError::Parse { .. } | CliError::Csv { .. })) => {
+                success = false;
+                results.push(json!({
+                    "file": path,
+                    "valid": false,
+                    "error": error.to_json()["error"],
+                }));
+                text.push(format!("{}: invalid, {error}", path.display()));
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(Output::Report {
+        json: json!({ "valid": success, "files": results }),
+        text: text.join("\n"),
+        success,
+    })
+}
+
+fn stats(path: &Path, format: Option<Format>) -> Result<Output, CliError> {
+    let labels = read(path, format)?;
+
+    let mut by_type = BTreeMap::new();
+    for label in labels.iter() {
+        *by_type.entry(label.ref_().type_tag()).or_insert(0usize) += 1;
+    }
+
+    let unlabeled = labels
+        .iter()
+        .filter(|label| label.label().is_none())
+        .count();
+    let unspendable = labels.iter().filter(|label| is_unspendable(label)).count();
+
+    let mut text = vec![format!("records: {}", labels.len())];
+    text.extend(
+        LABEL_TYPES
+            .iter()
+            .filter_map(|tag| Some(format!("  {tag}: {}", by_type.get(tag)?))),
+    );
+    text.push(format!("unlabeled: {unlabeled}"));
+    text.push(format!("unspendable outputs: {unspendable}"));
+
+    let json = json!({
+        "records": labels.len(),
+        "types": by_type,
+        "unlabeled": unlabeled,
+        "unspendable": unspendable,
+    });
+
+    Ok(Output::report(json, text.join("\n")))
+}
+
+fn convert(
+    input: &Path,
+    from: Option<Format>,
+    to: Format,
+    output: Option<&Path>,
+) -> Result<Output, CliError> {
+    let labels = read(input, from)?;
+    let (contents, skipped) = format::write(&labels, to)?;
+
+    let written = labels.len() - skipped;
+    let json = json!({ "records": written, "skipped": skipped });
+    let text = format!("converted {written} records, skipped {skipped}");
+
+    match write_output(output, contents.as_bytes(), json.clone(), text)? {
+        Output::Data if skipped > 0 => Ok(Output::PartialData {
+            json,
+            text: format!("{skipped} records can't be represented in the output format"),
+        }),
+        output => Ok(output),
+    }
+}
+
+fn merge(files: &[PathBuf], output: Option<&Path>) -> Result<Output, CliError> {
+    let mut merged = BTreeMap::<LabelRef, Label>::new();
+    let mut order = Vec::new();
+    let mut conflicts = 0;
+
+    for path in files {
+        for label in read(path, None)?.into_vec() {
+            let ref_ = label.ref_();
+
+            match merged.insert(ref_.clone(), label.clone()) {
+                Some(previous) if previous != label => conflicts += 1,
+                Some(_) => {}
+                None => order.push(ref_),
+            }
+        }
+    }
+
+    let labels = order
+        .into_iter()
+        .filter_map(|ref_| merged.remove(&ref_))
+        .collect::<Vec<_>>();
+    let labels = Labels::new(labels);
+
+    let (contents, _) = format::write(&labels, Format::Jsonl)?;
+    let json = json!({ "records": labels.len(), "conflicts": conflicts });
+    let text = format!(
+        "merged {} files into {} records, {conflicts} conflicts",
+        files.len(),
+        labels.len()
+    );
+
+    write_output(output, contents.as_bytes(), json, text)
+}
+
+fn diff(old: &Path, new: &Path) -> Result<Output, CliError> {
+    let old = read(old, None)?.into_map();
+    let new = read(new, None)?.into_map();
+
+    let refs = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    let mut text = Vec::new();
+
+    for ref_ in refs {
+        match (old.get(ref_), new.get(ref_)) {
+            (None, Some(label)) => {
+                text.push(format!("+ {}", line(label)?));
+                added.push(label);
+            }
+            (Some(label), None) => {
+                text.push(format!("- {}", line(label)?));
+                removed.push(label);
+            }
+            (Some(before), Some(after)) if before != after => {
+                text.push(format!("- {}", line(before)?));
+                text.push(format!("+ {}", line(after)?));
+                changed.push(json!({ "old": before, "new": after }));
+            }
+            _ => {}
+        }
+    }
+
+    let identical = added.is_empty() && removed.is_empty() && changed.is_empty();
+
+    Ok(Output::Report {
+        json: json!({
+            "identical": identical,
+            "added": added,
+            "removed": removed,
+            "changed": changed,
+        }),
+        text: if identical {
+            "labels are identical".to_string()
+        } else {
+            text.join("\n")
+        },
+        success: identical,
+    })
+}
+
+/// Records matching all of the set conditions
+struct Filter {
+    types: Vec<String>,
+    search: Option<String>,
+    unlabeled: bool,
+    unspendable: bool,
+}
+
+impl Filter {
+    fn new(
+        types: Vec<String>,
+        search: Option<String>,
+        unlabeled: bool,
+        unspendable: bool,
+    ) -> Result<Self, CliError> {
+        if let Some(unknown) = types.iter().find(|tag| !is_known_label_type(tag)) {
+            return Err(CliError::UnknownType(unknown.clone()));
+        }
+
+        Ok(Self {
+            types,
+            search: search.map(|search| search.to_lowercase()),
+            unlabeled,
+            unspendable,
+        })
+    }
+
+    fn matches(&self, label: &Label) -> bool {
+        if !self.types.is_empty() && !self.types.iter().any(|tag| tag == label.ref_().type_tag()) {
+            return false;
+        }
+
+        if self.unlabeled && label.label().is_some() {
+            return false;
+        }
+
+        if self.unspendable && !is_unspendable(label) {
+            return false;
+        }
+
+        let Some(search) = &self.search else {
+            return true;
+        };
+
+        let text = label.label().unwrap_or_default().to_lowercase();
+        let ref_ = label.ref_().to_string().to_lowercase();
+
+        text.contains(search) || ref_.contains(search)
+    }
+}
+
+fn filter_labels(path: &Path, filter: &Filter, output: Option<&Path>) -> Result<Output, CliError> {
+    let labels = read(path, None)?;
+    let total = labels.len();
+
+    let matched = labels
+        .into_vec()
+        .into_iter()
+        .filter(|label| filter.matches(label))
+        .collect::<Vec<_>>();
+    let matched = Labels::new(matched);
+
+    let (contents, _) = format::write(&matched, Format::Jsonl)?;
+    let json = json!({ "records": matched.len(), "total": total });
+    let text = format!("{} of {total} records matched", matched.len());
+
+    write_output(output, contents.as_bytes(), json, text)
+}
+
+fn edit(path: &Path, format: Option<Format>) -> Result<Output, CliError> {
+    let saves = editor::edit(path, Format::resolve(format, path)?)?;
+
+    let json = json!({ "saved": saves > 0 });
+    let text = match saves {
+        0 => "no changes saved".to_string(),
+        _ => format!("saved {}", path.display()),
+    };
+
+    Ok(Output::report(json, text))
+}
+
+fn encrypt(
+    input: &Path,
+    output: Option<&Path>,
+    recipients: &[String],
+    armor: bool,
+    passphrase_file: Option<&Path>,
+) -> Result<Output, CliError> {
+    let labels = read(input, None)?;
+
+    let encrypted = if recipients.is_empty() {
+        let passphrase = passphrase(passphrase_file, true)?;
+        EncryptedLabels::encrypt(&labels, &passphrase)?
+    } else {
+        let recipients = recipients
+            .iter()
+            .map(|recipient| encryption::parse_recipient(recipient))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        EncryptedLabels::encrypt_to_recipients(&labels, &recipients)?
+    };
+
+    // binary ciphertext is never written to a terminal
+    let contents = match armor || output.is_none() {
+        true => encrypted.to_armor()?.into_bytes(),
+        false => encrypted.into_bytes(),
+    };
+
+    let json = json!({ "records": labels.len() });
+    let text = format!("encrypted {} records", labels.len());
+
+    write_output(output, &contents, json, text)
+}
+
+fn decrypt(
+    input: &Path,
+    output: Option<&Path>,
+    identity: Option<&Path>,
+    passphrase_file: Option<&Path>,
+) -> Result<Output, CliError> {
+    let encoded = std::fs::read(input).map_err(|error| CliError::io(input, error))?;
+    let encrypted = EncryptedLabels::from_encoded(encoded)?;
+
+    let labels = match identity {
+        Some(path) => {
+            let identities = std::fs::read_to_string(path)
+                .map_err(|error| CliError::io(path, error))
+                .map(Zeroizing::new)?;
+
+            let identities = encryption::parse_identities(&identities)?;
+            encrypted.decrypt_with_identities(&identities)?
+        }
+        None => {
+            let passphrase = passphrase(passphrase_file, false)?;
+            encrypted.decrypt(&passphrase)?
+        }
+    };
+
+    let (contents, _) = format::write(&labels, Format::Jsonl)?;
+    let contents = Zeroizing::new(contents);
+
+    let json = json!({ "records": labels.len() });
+    let text = format!("decrypted {} records", labels.len());
+
+    write_output(output, contents.as_bytes(), json, text)
+}
+
+/// Read the passphrase from the file, the environment, or prompt for it
+fn passphrase(file: Option<&Path>, confirm: bool) -> Result<Zeroizing<String>, CliError> {
+    if let Some(path) = file {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| CliError::io(path, error))
+            .map(Zeroizing::new)?;
+
+        return Ok(Zeroizing::new(
+            contents.trim_end_matches(['\r', '\n']).to_string(),
+        ));
+    }
+
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+
+    let terminal = Path::new("terminal");
+    let passphrase = rpassword::prompt_password("Passphrase: ")
+        .map(Zeroizing::new)
+        .map_err(|error| CliError::io(terminal, error))?;
+
+    if confirm {
+        let confirmation = rpassword::prompt_password("Confirm passphrase: ")
+            .map(Zeroizing::new)
+            .map_err(|error| CliError::io(terminal, error))?;
+
+        if confirmation != passphrase {
+            return Err(CliError::PassphraseMismatch);
+        }
+    }
+
+    Ok(passphrase)
+}
+
+/// Write the contents to the output file and report, or print them to stdout
+fn write_output(
+    output: Option<&Path>,
+    contents: &[u8],
+    json: Value,
+    text: String,
+) -> Result<Output, CliError> {
+    let Some(path) = output else {
+        let stdout = Path::new("stdout");
+        std::io::stdout()
+            .write_all(contents)
+            .map_err(|error| CliError::io(stdout, error))?;
+
+        return Ok(Output::Data);
+    };
+
+    std::fs::write(path, contents).map_err(|error| CliError::io(path, error))?;
+    Ok(Output::report(json, text))
+}
+
+/// A single line JSON record, for diff output
+fn line(label: &Label) -> Result<String, CliError> {
+    Ok(serde_json::to_string(label).map_err(bip329::error::ExportError::from)?)
+}
+
+fn is_unspendable(label: &Label) -> bool {
+    matches!(label, Label::Output(record) if record.spendable == Some(false))
+}
@@ -0,0 +1,634 @@
+//! Interactive terminal editor for a label file.
+//!
+//! Records are listed grouped by transaction, like [`bip329::report`], with inputs and
+//! outputs nested under their transaction and the remaining records listed after.
+
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use bip329::compression::Compression;
+use bip329::{Label, Labels};
+use bitcoin::Txid;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style, Stylize as _},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    error::CliError,
+    format::{self, Format},
+};
+
+const HELP: &str = "↑↓ move  / search  enter edit  space toggle spendable  s save  q quit";
+
+/// A row of the record list
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Row {
+    /// A transaction without its own record, heading its inputs and outputs
+    Transaction(Txid),
+    /// The record at the index, nested under its transaction
+    Record { index: usize, nested: bool },
+}
+
+/// What the text input is used for
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    Search,
+    Edit(usize),
+}
+
+/// Whether the editor keeps running after a key press
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Action {
+    Continue,
+    Quit,
+}
+
+/// The editor state, independent of the terminal
+pub struct Editor {
+    path: PathBuf,
+    format: Format,
+    /// The compression of the file, kept when saving
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    compression: Option<Compression>,
+    labels: Labels,
+    rows: Vec<Row>,
+    list: ListState,
+    mode: Mode,
+    search: String,
+    input: String,
+    status: String,
+    modified: bool,
+    confirm_quit: bool,
+    saves: usize,
+}
+
+impl Editor {
+    pub fn open(path: &Path, format: Format) -> Result<Self, CliError> {
+        let labels = format::read(path, format)?;
+
+        let mut editor = Self {
+            path: path.to_path_buf(),
+            format,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compression: detect_compression(path, format)?,
+            labels,
+            rows: Vec::new(),
+            list: ListState::default(),
+            mode: Mode::Browse,
+            search: String::new(),
+            input: String::new(),
+            status: HELP.to_string(),
+            modified: false,
+            confirm_quit: false,
+            saves: 0,
+        };
+        editor.refresh();
+
+        Ok(editor)
+    }
+
+    /// Run the editor until it is quit, returning the number of times it saved
+    pub fn run(mut self, terminal: &mut DefaultTerminal) -> Result<usize, CliError> {
+        let tty = Path::new("terminal");
+
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|error| CliError::io(tty, error))?;
+
+            let Event::Key(key) = event::read().map_err(|error| CliError::io(tty, error))? else {
+                continue;
+            };
+
+            if key.kind == KeyEventKind::Press && self.handle_key(key) == Action::Quit {
+                return Ok(self.saves);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+
+        match self.mode {
+            Mode::Browse => return self.browse_key(key.code),
+            Mode::Search => match key.code {
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Esc => {
+                    self.input.clear();
+                    self.search.clear();
+                    self.mode = Mode::Browse;
+                    self.refresh();
+                }
+                code => {
+                    if self.edit_input(code) {
+                        self.search = self.input.to_lowercase();
+                        self.refresh();
+                    }
+                }
+            },
+            Mode::Edit(index) => match key.code {
+                KeyCode::Enter => {
+                    let text = self.input.trim();
+                    let label = (!text.is_empty()).then(|| text.to_string());
+                    self.set_label(index, label);
+                    self.mode = Mode::Browse;
+                }
+                KeyCode::Esc => self.mode = Mode::Browse,
+                code => {
+                    self.edit_input(code);
+                }
+            },
+        }
+
+        Action::Continue
+    }
+
+    fn browse_key(&mut self, code: KeyCode) -> Action {
+        let quit = matches!(code, KeyCode::Char('q') | KeyCode::Esc);
+        if !quit {
+            self.confirm_quit = false;
+        }
+
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-10),
+            KeyCode::PageDown => self.move_by(10),
+            KeyCode::Home => self.move_by(isize::MIN),
+            KeyCode::End => self.move_by(isize::MAX),
+            KeyCode::Char('/') => {
+                self.input = self.search.clone();
+                self.mode = Mode::Search;
+            }
+            KeyCode::Enter | KeyCode::Char('e') => {
+                if let Some(index) = self.selected_record() {
+                    self.input = self.labels[index].label().unwrap_or_default().to_string();
+                    self.mode = Mode::Edit(index);
+                }
+            }
+            KeyCode::Char(' ') => {
+                if let Some(index) = self.selected_record() {
+                    self.toggle_spendable(index);
+                }
+            }
+            KeyCode::Char('s') => self.save(),
+            _ if quit => {
+                if !self.modified || self.confirm_quit {
+                    return Action::Quit;
+                }
+
+                self.confirm_quit = true;
+                self.status = "Unsaved changes, press q again to quit without saving".into();
+            }
+            _ => {}
+        }
+
+        Action::Continue
+    }
+
+    /// Apply the key to the text input, returning whether the text changed
+    fn edit_input(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char(character) => self.input.push(character),
+            KeyCode::Backspace => return self.input.pop().is_some(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn move_by(&mut self, offset: isize) {
+        let Some(last) = self.rows.len().checked_sub(1) else {
+            return;
+        };
+
+        let selected = self.list.selected().unwrap_or_default();
+        let selected = selected.saturating_add_signed(offset).min(last);
+        self.list.select(Some(selected));
+    }
+
+    fn selected_record(&self) -> Option<usize> {
+        match self.rows.get(self.list.selected()?)? {
+            Row::Record { index, .. } => Some(*index),
+            Row::Transaction(_) => None,
+        }
+    }
+
+    fn set_label(&mut self, index: usize, text: Option<String>) {
+        let label = self.labels[index].label_mut();
+
+        if *label != text {
+            *label = text;
+            self.modified = true;
+            self.status = "Label updated".into();
+        }
+    }
+
+    fn toggle_spendable(&mut self, index: usize) {
+        let Label::Output(record) = &mut self.labels[index] else {
+            self.status = "Only outputs can be marked spendable".into();
+            return;
+        };
+
+        record.spendable = Some(!record.spendable());
+        self.modified = true;
+        self.status = match record.spendable() {
+            true => "Output marked spendable".into(),
+            false => "Output marked not spendable".into(),
+        };
+    }
+
+    fn save(&mut self) {
+        let result = self.encode().and_then(|(contents, skipped)| {
+            write_atomically(&self.path, &contents)?;
+            Ok(skipped)
+        });
+
+        self.status = match result {
+            Ok(skipped) => {
+                self.modified = false;
+                self.saves += 1;
+
+                let saved = self.labels.len() - skipped;
+                let path = self.path.display();
+                match skipped {
+                    0 => format!("Saved {saved} records to {path}"),
+                    _ => format!(
+                        "Saved {saved} records to {path}, skipped {skipped} the format can't represent"
+                    ),
+                }
+            }
+            Err(error) => format!("Save failed: {error}"),
+        };
+    }
+
+    /// The labels in the file's format and compression, and the number of records the
+    /// format can't represent
+    fn encode(&self) -> Result<(Vec<u8>, usize), CliError> {
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        if let Some(compression) = self.compression {
+            let contents = self
+                .labels
+                .export_to_writer_with_compression(Vec::new(), compression)?;
+            return Ok((contents, 0));
+        }
+
+        let (contents, skipped) = format::write(&self.labels, self.format)?;
+        Ok((contents.into_bytes(), skipped))
+    }
+
+    /// Rebuild the rows from the labels and the search, keeping the selected record
+    fn refresh(&mut self) {
+        let selected = self
+            .list
+            .selected()
+            .and_then(|row| self.rows.get(row))
+            .cloned();
+        self.rows = group(&self.labels, |label| self.matches(label));
+
+        let selected = selected
+            .and_then(|selected| self.rows.iter().position(|row| *row == selected))
+            .or((!self.rows.is_empty()).then_some(0));
+        self.list.select(selected);
+    }
+
+    fn matches(&self, label: &Label) -> bool {
+        if self.search.is_empty() {
+            return true;
+        }
+
+        let text = label.label().unwrap_or_default().to_lowercase();
+        let ref_ = label.ref_().to_string().to_lowercase();
+
+        text.contains(&self.search) || ref_.contains(&self.search)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list_area, input_area, status_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let items = self
+            .rows
+            .iter()
+            .map(|row| Self::row_line(&self.labels, row))
+            .map(ListItem::new)
+            .collect::<Vec<_>>();
+
+        let modified = if self.modified { " [modified]" } else { "" };
+        let title = format!(" {}{modified} ", self.path.display());
+
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        let input = match &self.mode {
+            Mode::Browse if self.search.is_empty() => Line::default(),
+            Mode::Browse => Line::from(format!("search: {}", self.search)).dim(),
+            Mode::Search => Line::from(format!("search: {}█", self.input)),
+            Mode::Edit(_) => Line::from(format!("label: {}█", self.input)),
+        };
+        frame.render_widget(Paragraph::new(input), input_area);
+        frame.render_widget(Paragraph::new(self.status.as_str()).dim(), status_area);
+    }
+
+    fn row_line<'a>(labels: &'a Labels, row: &Row) -> Line<'a> {
+        let (index, nested) = match row {
+            Row::Transaction(txid) => return Line::from(format!("tx      {txid}")).dim(),
+            Row::Record { index, nested } => (*index, *nested),
+        };
+
+        let label = &labels[index];
+        let indent = if nested { "  " } else { "" };
+        let ref_ = match label {
+            Label::Input(record) => format!("input :{}", record.ref_.vout),
+            Label::Output(record) => format!("output :{}", record.ref_.vout),
+            label => format!("{:<7} {}", label.ref_().type_tag(), label.ref_()),
+        };
+
+        let mut spans = vec![Span::raw(format!("{indent}{ref_}  "))];
+        match label.label() {
+            Some(text) => spans.push(Span::raw(text).bold()),
+            None => spans.push(Span::raw("(no label)").dim()),
+        }
+
+        if let Label::Output(record) = label {
+            if !record.spendable() {
+                spans.push(Span::raw("  not spendable").red());
+            }
+        }
+
+        Line::from(spans)
+    }
+}
+
+/// The rows of the matching labels, grouped by transaction like the report, then all
+/// other records
+fn group(labels: &Labels, matches: impl Fn(&Label) -> bool) -> Vec<Row> {
+    let groups = labels.group_by_transaction();
+    let mut rows = Vec::new();
+
+    for transaction in groups.transactions {
+        let record_matches = transaction
+            .record
+            .is_some_and(|index| matches(&labels[index]));
+
+        let children = transaction
+            .inputs
+            .into_iter()
+            .chain(transaction.outputs)
+            .filter(|&index| record_matches || matches(&labels[index]))
+            .map(|index| Row::Record {
+                index,
+                nested: true,
+            })
+            .collect::<Vec<_>>();
+
+        if !record_matches && children.is_empty() {
+            continue;
+        }
+
+        rows.push(match transaction.record {
+            Some(index) => Row::Record {
+                index,
+                nested: false,
+            },
+            None => Row::Transaction(transaction.txid),
+        });
+        rows.extend(children);
+    }
+
+    let others = groups
+        .others
+        .into_iter()
+        .filter(|&index| matches(&labels[index]));
+    rows.extend(others.map(|index| Row::Record {
+        index,
+        nested: false,
+    }));
+    rows
+}
+
+/// The compression of a JSONL file, which is decompressed when it is read
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+fn detect_compression(path: &Path, format: Format) -> Result<Option<Compression>, CliError> {
+    use std::io::Read as _;
+
+    if format != Format::Jsonl {
+        return Ok(None);
+    }
+
+    let mut magic = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|file| file.take(4).read_to_end(&mut magic))
+        .map_err(|error| CliError::io(path, error))?;
+
+    Ok(Compression::detect(&magic))
+}
+
+/// Replace the file with the contents, writing them to a temporary file in the same
+/// directory first, so the file is never left partially written
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), CliError> {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    let temporary = path.with_file_name(file_name);
+
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&temporary)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temporary, path)
+    };
+
+    write().map_err(|error| {
+        let _ = std::fs::remove_file(&temporary);
+        CliError::io(path, error)
+    })
+}
+
+/// Open the label file in the editor, returning the number of times it was saved
+pub fn edit(path: &Path, format: Format) -> Result<usize, CliError> {
+    let editor = Editor::open(path, format)?;
+
+    let mut terminal = ratatui::init();
+    let result = editor.run(&mut terminal);
+    ratatui::restore();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn press(editor: &mut Editor, codes: impl IntoIterator<Item = KeyCode>) -> Action {
+        let mut action = Action::Continue;
+        for code in codes {
+            action = editor.handle_key(KeyEvent::from(code));
+        }
+        action
+    }
+
+    fn type_text(text: &str) -> Vec<KeyCode> {
+        text.chars().map(KeyCode::Char).collect()
+    }
+
+    fn open_copy() -> (tempfile::TempDir, PathBuf, Editor) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("labels.jsonl");
+        std::fs::copy("tests/data/labels.jsonl", &path).unwrap();
+
+        let editor = Editor::open(&path, Format::Jsonl).unwrap();
+        (dir, path, editor)
+    }
+
+    #[test]
+    fn rows_are_grouped_by_transaction() {
+        let (_dir, _path, editor) = open_copy();
+
+        let records = editor
+            .rows
+            .iter()
+            .filter(|row| matches!(row, Row::Record { .. }))
+            .count();
+        assert_eq!(records, editor.labels.len());
+
+        let mut rows = editor.rows.iter().peekable();
+        while let Some(row) = rows.next() {
+            let Row::Record { index, nested } = row else {
+                continue;
+            };
+
+            if let Label::Input(_) | Label::Output(_) = &editor.labels[*index] {
+                assert!(nested);
+            }
+
+            if let Some(Row::Record {
+                index: next,
+                nested: true,
+            }) = rows.peek()
+            {
+                let txid = |label: &Label| match label {
+                    Label::Transaction(record) => Some(record.ref_),
+                    Label::Input(record) => Some(record.ref_.txid),
+                    Label::Output(record) => Some(record.ref_.txid),
+                    _ => None,
+                };
+                assert_eq!(txid(&editor.labels[*index]), txid(&editor.labels[*next]));
+            }
+        }
+    }
+
+    #[test]
+    fn search_edit_toggle_and_save() {
+        let (_dir, path, mut editor) = open_copy();
+
+        press(&mut editor, [KeyCode::Char('/')]);
+        press(&mut editor, type_text("(RECEIVED)"));
+        press(&mut editor, [KeyCode::Enter]);
+        assert!(!editor.rows.is_empty());
+        assert!(editor.rows.len() < editor.labels.len());
+
+        let output = (0..editor.rows.len())
+            .find_map(|row| {
+                editor.list.select(Some(row));
+                let index = editor.selected_record()?;
+                matches!(editor.labels[index], Label::Output(_)).then_some(index)
+            })
+            .unwrap();
+        let spendable = editor.labels[output].clone();
+
+        press(&mut editor, [KeyCode::Char(' ')]);
+        press(&mut editor, [KeyCode::Enter]);
+        press(&mut editor, (0..100).map(|_| KeyCode::Backspace));
+        press(&mut editor, type_text("cold storage"));
+        press(&mut editor, [KeyCode::Enter]);
+
+        assert_eq!(press(&mut editor, [KeyCode::Char('q')]), Action::Continue);
+        press(&mut editor, [KeyCode::Char('s')]);
+        assert_eq!(press(&mut editor, [KeyCode::Char('q')]), Action::Quit);
+
+        let saved = Labels::try_from_file(&path).unwrap();
+        let Label::Output(record) = &saved[output] else {
+            panic!("records keep their order");
+        };
+        let Label::Output(before) = spendable else {
+            unreachable!()
+        };
+
+        assert_eq!(record.ref_, before.ref_);
+        assert_eq!(record.label.as_deref(), Some("cold storage"));
+        assert_eq!(record.spendable(), !before.spendable());
+        assert_eq!(saved.len(), editor.labels.len());
+        assert!(!path.with_file_name("labels.jsonl.tmp").exists());
+    }
+
+    #[test]
+    fn electrum_save_reports_skipped_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("labels.json");
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let (contents, _) = format::write(&labels, Format::Electrum).unwrap();
+        std::fs::write(&path, contents).unwrap();
+
+        let mut editor = Editor::open(&path, Format::Electrum).unwrap();
+        let records = editor.labels.len();
+
+        editor.save();
+        assert_eq!(
+            editor.status,
+            format!("Saved {records} records to {}", path.display())
+        );
+
+        editor.set_label(0, None);
+        editor.save();
+        assert!(editor
+            .status
+            .ends_with(", skipped 1 the format can't represent"));
+        assert_eq!(
+            format::read(&path, Format::Electrum).unwrap().len(),
+            records - 1
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn save_keeps_compression() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("labels.jsonl");
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        labels
+            .export_to_file_with_compression(&path, Compression::Gzip)
+            .unwrap();
+
+        let mut editor = Editor::open(&path, Format::Jsonl).unwrap();
+        assert_eq!(editor.labels, labels);
+
+        editor.set_label(0, Some("edited".into()));
+        editor.save();
+
+        let saved = std::fs::read(&path).unwrap();
+        assert_eq!(Compression::detect(&saved), Some(Compression::Gzip));
+        assert_eq!(
+            Labels::try_from_file(&path).unwrap()[0].label(),
+            Some("edited")
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use bip329::error::{EncryptionError, ExportError, ParseError};
+use serde_json::{json, Value};
+
+/// Validation failed, or the compared label files differ
+pub const EXIT_FAILURE: u8 = 1;
+
+/// A file could not be read or written
+pub const EXIT_IO: u8 = 3;
+
+/// An input file is not valid in its format
+pub const EXIT_INVALID_INPUT: u8 = 4;
+
+/// The passphrase or identities can't decrypt the labels
+pub const EXIT_WRONG_KEY: u8 = 5;
+
+/// Any other encryption or decryption error
+pub const EXIT_ENCRYPTION: u8 = 6;
+
+/// Errors that stop a command
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("Unable to read or write {}: {error}", .path.display())]
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    #[error("Invalid labels in {}: {error}", .path.display())]
+    Parse { path: PathBuf, error: ParseError },
+
+    #[error("Invalid CSV in {}: {error}", .path.display())]
+    Csv { path: PathBuf, error: csv::Error },
+
+    #[error("Unknown file format for {}, use --format", .0.display())]
+    UnknownFormat(PathBuf),
+
+    #[error("Unknown label type {0}")]
+    UnknownType(String),
+
+    #[error("Not a txid or address: {0}")]
+    InvalidRef(String),
+
+    #[error("Passphrases do not match")]
+    PassphraseMismatch,
+
+    #[error("Unable to export labels: {0}")]
+    Export(#[from] ExportError),
+
+    #[error("{0}")]
+    Encryption(#[from] EncryptionError),
+}
+
+impl CliError {
+    pub fn io(path: &Path, error: std::io::Error) -> Self {
+        Self::Io {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+
+    pub fn parse(path: &Path, error: ParseError) -> Self {
+        Self::Parse {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+
+    /// The process exit code for the error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Io { .. } => EXIT_IO,
+            Self::Parse { .. } | Self::Csv { .. } | Self::UnknownFormat(_) => EXIT_INVALID_INPUT,
+            Self::UnknownType(_) | Self::InvalidRef(_) => EXIT_INVALID_INPUT,
+            Self::PassphraseMismatch => EXIT_WRONG_KEY,
+            Self::Export(_) => EXIT_IO,
+            Self::Encryption(error) => match error {
+                EncryptionError::WrongPassphrase | EncryptionError::NoMatchingKey => EXIT_WRONG_KEY,
+                EncryptionError::ParseError(_) | EncryptionError::Utf8Error(_) => {
+                    EXIT_INVALID_INPUT
+                }
+                _ => EXIT_ENCRYPTION,
+            },
+        }
+    }
+
+    /// A short, stable name for the kind of error, for JSON output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Parse { .. } | Self::Csv { .. } => "invalid_labels",
+            Self::UnknownFormat(_) => "unknown_format",
+            Self::UnknownType(_) => "unknown_type",
+            Self::InvalidRef(_) => "invalid_ref",
+            Self::PassphraseMismatch => "passphrase_mismatch",
+            Self::Export(_) => "export",
+            Self::Encryption(error) => match error {
+                EncryptionError::WrongPassphrase => "wrong_passphrase",
+                EncryptionError::NoMatchingKey => "no_matching_key",
+                EncryptionError::ExcessiveWorkFactor { .. } => "excessive_work_factor",
+                EncryptionError::CorruptedCiphertext(_) => "corrupted_ciphertext",
+                EncryptionError::ParseError(_) | EncryptionError::Utf8Error(_) => "invalid_labels",
+                _ => "encryption",
+            },
+        }
+    }
+
+    /// The line of the invalid record, when known
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Self::Parse { error, .. } | Self::Encryption(EncryptionError::ParseError(error)) => {
+                error.line()
+            }
+            Self::Csv { error, .. } => error.position().map(|position| position.line() as usize),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": {
+                "kind": self.kind(),
+                "message": self.to_string(),
+                "line": self.line(),
+            }
+        })
+    }
+}
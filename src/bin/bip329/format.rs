@@ -0,0 +1,229 @@
+//! Label file formats supported by the command line tool.
+
+use std::{collections::BTreeMap, path::Path, str::FromStr as _};
+
+use bip329::{
+    error::{ExportError, ParseError},
+    AddressRecord, Label, Labels, TransactionRecord,
+};
+use bitcoin::{address::NetworkUnchecked, Address, Txid};
+use serde_json::{Map, Value};
+
+use crate::error::CliError;
+
+const CSV_HEADER: [&str; 5] = ["type", "ref", "label", "origin", "spendable"];
+
+/// A label file format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// BIP329 JSON lines
+    Jsonl,
+    /// Comma separated values, with a `type,ref,label,origin,spendable` header
+    Csv,
+    /// Electrum wallet label export, a JSON object of txids and addresses to labels
+    Electrum,
+}
+
+impl Format {
+    /// Detect the format from the file extension
+    pub fn detect(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "jsonl" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Electrum),
+            _ => None,
+        }
+    }
+
+    /// The given format, or the format detected from the file extension
+    pub fn resolve(format: Option<Self>, path: &Path) -> Result<Self, CliError> {
+        format
+            .or_else(|| Self::detect(path))
+            .ok_or_else(|| CliError::UnknownFormat(path.to_path_buf()))
+    }
+}
+
+/// Read labels from a file in the given format
+pub fn read(path: &Path, format: Format) -> Result<Labels, CliError> {
+    if format == Format::Jsonl {
+        return Labels::try_from_file(path).map_err(|error| match error {
+            ParseError::FileReadError(error) => CliError::io(path, error),
+            error => CliError::parse(path, error),
+        });
+    }
+
+    let contents = std::fs::read_to_string(path).map_err(|error| CliError::io(path, error))?;
+
+    match format {
+        Format::Jsonl => unreachable!("handled above"),
+        Format::Csv => read_csv(path, &contents),
+        Format::Electrum => read_electrum(path, &contents),
+    }
+}
+
+/// Write labels in the given format
+///
+/// Returns the encoded labels, and the number of records the format can't represent.
+pub fn write(labels: &Labels, format: Format) -> Result<(String, usize), CliError> {
+    match format {
+        Format::Jsonl => {
+            let mut contents = labels.export()?;
+            if !contents.is_empty() {
+                contents.push('\n');
+            }
+
+            Ok((contents, 0))
+        }
+        Format::Csv => Ok((write_csv(labels)?, 0)),
+        Format::Electrum => write_electrum(labels),
+    }
+}
+
+fn read_csv(path: &Path, contents: &str) -> Result<Labels, CliError> {
+    let csv_error = |error| CliError::Csv {
+        path: path.to_path_buf(),
+        error,
+    };
+
+    let mut reader = csv::Reader::from_reader(contents.as_bytes());
+    let headers = reader.headers().map_err(csv_error)?.clone();
+
+    let mut labels = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let line = record
+            .position()
+            .map_or(0, |position| position.line() as usize);
+
+        // empty cells are omitted fields, `spendable` accepts the string booleans
+        let fields = headers
+            .iter()
+            .zip(record.iter())
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(header, value)| (header.to_string(), Value::from(value)))
+            .collect::<Map<_, _>>();
+
+        let label = serde_json::from_value(Value::Object(fields))
+            .map_err(|source| CliError::parse(path, ParseError::InvalidLine { line, source }))?;
+
+        labels.push(label);
+    }
+
+    Ok(Labels::new(labels))
+}
+
+fn write_csv(labels: &Labels) -> Result<String, CliError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let to_io = |error: csv::Error| ExportError::FileWriteError(error.into());
+
+    writer.write_record(CSV_HEADER).map_err(to_io)?;
+
+    for label in labels.iter() {
+        let (origin, spendable) = match label {
+            Label::Transaction(record) => (record.origin.as_deref(), None),
+            Label::Output(record) => (None, record.spendable),
+            _ => (None, None),
+        };
+
+        let ref_ = label.ref_().to_string();
+        let spendable = spendable.map(|spendable| spendable.to_string());
+
+        writer
+            .write_record([
+                label.ref_().type_tag(),
+                &ref_,
+                label.label().unwrap_or_default(),
+                origin.unwrap_or_default(),
+                spendable.as_deref().unwrap_or_default(),
+            ])
+            .map_err(to_io)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|error| ExportError::FileWriteError(error.into_error()))?;
+
+    Ok(String::from_utf8(bytes).expect("CSV of UTF-8 strings is valid UTF-8"))
+}
+
+fn read_electrum(path: &Path, contents: &str) -> Result<Labels, CliError> {
+    let entries: BTreeMap<String, String> = serde_json::from_str(contents)
+        .map_err(|error| CliError::parse(path, ParseError::ParseError(error)))?;
+
+    let labels = entries
+        .into_iter()
+        .map(|(ref_, label)| {
+            if let Ok(txid) = Txid::from_str(&ref_) {
+                return Ok(TransactionRecord {
+                    ref_: txid,
+                    label: Some(label),
+                    origin: None,
+                }
+                .into());
+            }
+
+            match Address::<NetworkUnchecked>::from_str(&ref_) {
+                Ok(address) => Ok(AddressRecord {
+                    ref_: address,
+                    label: Some(label),
+                }
+                .into()),
+                Err(_) => Err(CliError::InvalidRef(ref_)),
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Labels::new(labels))
+}
+
+fn write_electrum(labels: &Labels) -> Result<(String, usize), CliError> {
+    let mut entries = Map::new();
+    let mut skipped = 0;
+
+    for label in labels.iter() {
+        match (label, label.label()) {
+            (Label::Transaction(_) | Label::Address(_), Some(text)) => {
+                entries.insert(label.ref_().to_string(), text.into());
+            }
+            _ => skipped += 1,
+        }
+    }
+
+    let mut contents = serde_json::to_string_pretty(&entries).map_err(ExportError::from)?;
+    contents.push('\n');
+
+    Ok((contents, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn csv_loop_back() {
+        let path = Path::new("tests/data/labels.jsonl");
+        let labels = read(path, Format::Jsonl).unwrap();
+
+        let (csv, skipped) = write(&labels, Format::Csv).unwrap();
+        assert_eq!(skipped, 0);
+        assert!(csv.starts_with("type,ref,label,origin,spendable\n"));
+
+        assert_eq!(read_csv(Path::new("labels.csv"), &csv).unwrap(), labels);
+    }
+
+    #[test]
+    fn electrum_keeps_transaction_and_address_labels() {
+        let path = Path::new("tests/data/labels.jsonl");
+        let labels = read(path, Format::Jsonl).unwrap();
+
+        let (electrum, skipped) = write(&labels, Format::Electrum).unwrap();
+        let imported = read_electrum(Path::new("labels.json"), &electrum).unwrap();
+
+        assert_eq!(imported.len() + skipped, labels.len());
+        for label in imported.iter() {
+            assert!(matches!(label, Label::Transaction(_) | Label::Address(_)));
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! The `bip329` command line tool, requires the `cli` feature.
+//!
+//! Exits with `0` on success, `1` when validation fails or compared files differ,
+//! `2` for invalid arguments, and the codes in [`error`] for other errors. With
+//! `--json`, results and errors are printed to stdout as a single JSON object.
+
+mod commands;
+mod editor;
+mod error;
+mod format;
+
+use std::{
+    io::{self, Write as _},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand};
+
+use crate::{commands::Output, format::Format};
+
+/// Inspect, convert, merge and encrypt BIP329 label files
+#[derive(Parser)]
+#[command(name = "bip329", version)]
+struct Cli {
+    /// Print results and errors as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check that label files are valid
+    Validate {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Input format, detected from the file extension by default
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+    },
+    /// Count the records in a label file
+    Stats {
+        file: PathBuf,
+        /// Input format, detected from the file extension by default
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+    },
+    /// Convert a label file between JSONL, CSV and Electrum formats
+    Convert {
+        input: PathBuf,
+        /// Input format, detected from the file extension by default
+        #[arg(long, value_enum)]
+        from: Option<Format>,
+        /// Output format
+        #[arg(long, value_enum)]
+        to: Format,
+        /// Output file, stdout by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Merge label files, records in later files replace records with the same ref
+    Merge {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Output file, stdout by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Show the records added, removed and changed between two label files
+    Diff { old: PathBuf, new: PathBuf },
+    /// Print the records matching all of the given filters
+    Filter {
+        file: PathBuf,
+        /// Only records of these types, e.g. `tx` or `output`
+        #[arg(long = "type")]
+        types: Vec<String>,
+        /// Only records whose ref or label contains the text, ignoring case
+        #[arg(long)]
+        search: Option<String>,
+        /// Only records without a label
+        #[arg(long)]
+        unlabeled: bool,
+        /// Only outputs marked as not spendable
+        #[arg(long)]
+        unspendable: bool,
+        /// Output file, stdout by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Browse and edit a label file in the terminal
+    Edit {
+        file: PathBuf,
+        /// File format, detected from the file extension by default
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+    },
+    /// Encrypt a label file with a passphrase, or to age recipients
+    Encrypt {
+        input: PathBuf,
+        /// Output file, ASCII armored to stdout by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Encrypt to an age or SSH public key instead of a passphrase, repeatable
+        #[arg(short, long = "recipient")]
+        recipients: Vec<String>,
+        /// Write ASCII armor instead of binary
+        #[arg(short, long)]
+        armor: bool,
+        /// Read the passphrase from a file, instead of `BIP329_PASSPHRASE` or a prompt
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+    /// Decrypt an encrypted label file to JSONL
+    Decrypt {
+        input: PathBuf,
+        /// Output file, stdout by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Decrypt with the identities in an age identity file instead of a passphrase
+        #[arg(short, long)]
+        identity: Option<PathBuf>,
+        /// Read the passphrase from a file, instead of `BIP329_PASSPHRASE` or a prompt
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match commands::run(cli.command) {
+        Ok(Output::Data) => ExitCode::SUCCESS,
+        Ok(Output::PartialData { json, text }) => {
+            // stdout holds the labels
+            match cli.json {
+                true => eprintln!("{json}"),
+                false => eprintln!("warning: {text}"),
+            }
+
+            ExitCode::SUCCESS
+        }
+        Ok(Output::Report {
+            json,
+            text,
+            success,
+        }) => {
+            // a closed stdout, e.g. piped into `head`, is not an error
+            let _ = match cli.json {
+                true => writeln!(io::stdout(), "{json}"),
+                false => writeln!(io::stdout(), "{text}"),
+            };
+
+            match success {
+                true => ExitCode::SUCCESS,
+                false => ExitCode::from(error::EXIT_FAILURE),
+            }
+        }
+        Err(error) => {
+            if cli.json {
+                let _ = writeln!(io::stdout(), "{}", error.to_json());
+            } else {
+                eprintln!("error: {error}");
+            }
+
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
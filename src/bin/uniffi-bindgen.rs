@@ -0,0 +1,7 @@
+//! Generates the Kotlin and Swift bindings, requires the `uniffi` feature.
+//!
+//! `cargo run --features uniffi --bin uniffi-bindgen generate --library <path> --language kotlin`
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,198 @@
+//! Module for encoding labels in a compact binary format.
+//!
+//! [`Label`] uses an internally tagged serde representation, which only works with
+//! self-describing formats like JSON. This module encodes labels with
+//! [postcard](https://docs.rs/postcard) instead, for caching label sets on
+//! constrained devices.
+//!
+//! The encoding is the 4 byte magic `B329`, followed by a 1 byte format version,
+//! followed by the postcard encoded list of records.
+
+use bitcoin::{address::NetworkUnchecked, Address, OutPoint, Txid};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::BinaryError, AddressRecord, ExtendedPublicKeyRecord, InputRecord, Label, Labels,
+    OutputRecord, PublicKeyRecord, SilentPaymentsScanRecord, TransactionRecord,
+};
+
+/// The magic bytes at the start of every binary label set
+pub const MAGIC: [u8; 4] = *b"B329";
+
+/// The binary format version written by [`Labels::to_binary`]
+pub const VERSION: u8 = 1;
+
+impl Labels {
+    /// Encode the Labels struct in the compact binary format.
+    pub fn to_binary(&self) -> Result<Vec<u8>, BinaryError> {
+        let records = self.iter().map(BinaryLabel::from).collect::<Vec<_>>();
+
+        let mut bytes = Vec::from(MAGIC);
+        bytes.push(VERSION);
+
+        Ok(postcard::to_extend(&records, bytes)?)
+    }
+
+    /// Create a new Labels struct from the compact binary format.
+    pub fn try_from_binary(bytes: &[u8]) -> Result<Self, BinaryError> {
+        let body = bytes
+            .strip_prefix(&MAGIC)
+            .ok_or(BinaryError::InvalidHeader)?;
+
+        let (version, body) = body.split_first().ok_or(BinaryError::InvalidHeader)?;
+        if *version != VERSION {
+            return Err(BinaryError::UnsupportedVersion(*version));
+        }
+
+        let records: Vec<BinaryLabel> = postcard::from_bytes(body)?;
+        let labels = records.into_iter().map(Label::from).collect();
+
+        Ok(Self::new(labels))
+    }
+}
+
+/// Externally tagged mirror of [`Label`] for non-self-describing formats
+///
+/// Variant and field order is part of the wire format, new variants must only be
+/// appended at the end
+#[derive(Serialize, Deserialize)]
+enum BinaryLabel {
+    Transaction {
+        ref_: Txid,
+        label: Option<String>,
+        origin: Option<String>,
+    },
+    Address {
+        ref_: Address<NetworkUnchecked>,
+        label: Option<String>,
+    },
+    PublicKey {
+        ref_: String,
+        label: Option<String>,
+    },
+    Input {
+        ref_: OutPoint,
+        label: Option<String>,
+    },
+    Output {
+        ref_: OutPoint,
+        label: Option<String>,
+        spendable: Option<bool>,
+    },
+    ExtendedPublicKey {
+        ref_: String,
+        label: Option<String>,
+    },
+    SilentPaymentsScan {
+        ref_: String,
+        label: Option<String>,
+    },
+}
+
+impl From<&Label> for BinaryLabel {
+    fn from(label: &Label) -> Self {
+        match label.clone() {
+            Label::Transaction(record) => Self::Transaction {
+                ref_: record.ref_,
+                label: record.label,
+                origin: record.origin,
+            },
+            Label::Address(record) => Self::Address {
+                ref_: record.ref_,
+                label: record.label,
+            },
+            Label::PublicKey(record) => Self::PublicKey {
+                ref_: record.ref_,
+                label: record.label,
+            },
+            Label::Input(record) => Self::Input {
+                ref_: record.ref_,
+                label: record.label,
+            },
+            Label::Output(record) => Self::Output {
+                ref_: record.ref_,
+                label: record.label,
+                spendable: record.spendable,
+            },
+            Label::ExtendedPublicKey(record) => Self::ExtendedPublicKey {
+                ref_: record.ref_,
+                label: record.label,
+            },
+            Label::SilentPaymentsScan(record) => Self::SilentPaymentsScan {
+                ref_: record.ref_,
+                label: record.label,
+            },
+        }
+    }
+}
+
+impl From<BinaryLabel> for Label {
+    fn from(label: BinaryLabel) -> Self {
+        match label {
+            BinaryLabel::Transaction {
+                ref_,
+                label,
+                origin,
+            } => TransactionRecord {
+                ref_,
+                label,
+                origin,
+            }
+            .into(),
+            BinaryLabel::Address { ref_, label } => AddressRecord { ref_, label }.into(),
+            BinaryLabel::PublicKey { ref_, label } => {
+                Label::PublicKey(PublicKeyRecord { ref_, label })
+            }
+            BinaryLabel::Input { ref_, label } => InputRecord { ref_, label }.into(),
+            BinaryLabel::Output {
+                ref_,
+                label,
+                spendable,
+            } => OutputRecord {
+                ref_,
+                label,
+                spendable,
+            }
+            .into(),
+            BinaryLabel::ExtendedPublicKey { ref_, label } => {
+                Label::ExtendedPublicKey(ExtendedPublicKeyRecord { ref_, label })
+            }
+            BinaryLabel::SilentPaymentsScan { ref_, label } => {
+                SilentPaymentsScanRecord { ref_, label }.into()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_is_smaller_than_jsonl() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let binary = labels.to_binary().unwrap();
+        let jsonl = labels.export().unwrap();
+
+        assert!(binary.starts_with(b"B329\x01"));
+        assert!(binary.len() < jsonl.len());
+    }
+
+    #[test]
+    fn rejects_invalid_header() {
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let mut binary = labels.to_binary().unwrap();
+
+        assert!(matches!(
+            Labels::try_from_binary(&binary[1..]),
+            Err(BinaryError::InvalidHeader)
+        ));
+
+        binary[4] = 2;
+        assert!(matches!(
+            Labels::try_from_binary(&binary),
+            Err(BinaryError::UnsupportedVersion(2))
+        ));
+    }
+}
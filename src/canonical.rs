@@ -0,0 +1,102 @@
+use std::{cmp::Ordering, collections::BTreeMap, path::Path};
+
+use serde_json::Value;
+
+use crate::{error::ExportError, Label, Labels};
+
+impl Labels {
+    /// Export the Labels struct to a canonical, byte-for-byte deterministic string.
+    ///
+    /// Two label sets containing the same records always produce the same bytes,
+    /// regardless of record order or how the records were originally written, so the
+    /// output is suitable for hashing and signing.
+    ///
+    /// The canonical form is:
+    ///
+    /// 1. One JSON object per record, with no whitespace between tokens.
+    /// 2. Object keys sorted in ascending byte order, which for BIP329 records is
+    ///    `label`, `origin`, `ref`, `spendable`, `type`.
+    /// 3. Keys whose value would be `null` are omitted, as are any fields not
+    ///    supported by this crate.
+    /// 4. Output records always have `spendable` as a JSON boolean, `true` when the
+    ///    field was omitted, never as a string.
+    /// 5. Strings are escaped as in [RFC 8785](https://www.rfc-editor.org/rfc/rfc8785):
+    ///    only `"`, `\`, and control characters are escaped, using `\b`, `\t`, `\n`,
+    ///    `\f`, `\r` where available and lowercase `\u00xx` otherwise. All other
+    ///    characters are written as UTF-8.
+    /// 6. Records are ordered by `type` in the order `tx`, `addr`, `pubkey`, `input`,
+    ///    `output`, `xpub`, `spscan`, then by the `ref` string in ascending byte order,
+    ///    then by the serialized record itself in ascending byte order.
+    /// 7. Every record, including the last, is followed by a single `\n`. An empty
+    ///    label set exports to an empty string.
+    ///
+    /// `ref` values are written as this crate serializes them: lowercase hex txids,
+    /// `txid:vout` outpoints, and addresses in their standard string encoding.
+    pub fn export_canonical(&self) -> Result<String, ExportError> {
+        let mut records = self
+            .iter()
+            .map(|label| Ok((label, canonical_record(label)?)))
+            .collect::<Result<Vec<_>, ExportError>>()?;
+
+        records.sort_by(|(label_a, record_a), (label_b, record_b)| {
+            compare_labels(label_a, label_b).then_with(|| record_a.cmp(record_b))
+        });
+
+        let contents = records
+            .into_iter()
+            .fold(String::new(), |mut acc, (_, record)| {
+                acc.push_str(&record);
+                acc.push('\n');
+                acc
+            });
+
+        Ok(contents)
+    }
+
+    /// Export the Labels struct to a file in the canonical form.
+    ///
+    /// See [`Labels::export_canonical`] for the format.
+    pub fn export_canonical_to_file(&self, path: impl AsRef<Path>) -> Result<(), ExportError> {
+        let contents = self.export_canonical()?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+fn canonical_record(label: &Label) -> Result<String, ExportError> {
+    let Value::Object(fields) = serde_json::to_value(label)? else {
+        unreachable!("labels always serialize to a JSON object");
+    };
+
+    // BTreeMap keeps keys in byte order regardless of serde_json features
+    let mut fields = fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .collect::<BTreeMap<String, Value>>();
+
+    if let Label::Output(record) = label {
+        fields.insert("spendable".to_string(), Value::Bool(record.spendable()));
+    }
+
+    Ok(serde_json::to_string(&fields)?)
+}
+
+fn compare_labels(a: &Label, b: &Label) -> Ordering {
+    let (ref_a, ref_b) = (a.ref_(), b.ref_());
+
+    type_rank(a)
+        .cmp(&type_rank(b))
+        .then_with(|| ref_a.to_string().cmp(&ref_b.to_string()))
+}
+
+fn type_rank(label: &Label) -> u8 {
+    match label {
+        Label::Transaction(_) => 0,
+        Label::Address(_) => 1,
+        Label::PublicKey(_) => 2,
+        Label::Input(_) => 3,
+        Label::Output(_) => 4,
+        Label::ExtendedPublicKey(_) => 5,
+        Label::SilentPaymentsScan(_) => 6,
+    }
+}
@@ -0,0 +1,550 @@
+//! C bindings, behind the `capi` feature.
+//!
+//! The header is generated with cbindgen into `include/bip329.h`, see `cbindgen.toml`.
+//! Labels and records are opaque handles owned by the caller, and strings returned by
+//! the library are freed with `bip329_string_free()`. Functions that can fail return an
+//! [`ErrorCode`], and the message of the last failed call on the calling thread is
+//! available from `bip329_last_error_message()`.
+//!
+//! Strings passed in must be NUL-terminated UTF-8.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CStr, CString},
+    fmt::Display,
+    ptr, slice,
+};
+
+use crate::{
+    encryption::EncryptedLabels,
+    error::{EncryptionError, ExportError, ParseError},
+    Label, LabelParseOptions, Labels,
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// The result of a fallible call
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer,
+    /// A string argument was not valid UTF-8, or a returned string would contain a NUL byte
+    InvalidString,
+    /// The labels are not valid BIP329
+    ParseError,
+    /// The labels file could not be read
+    FileReadError,
+    /// The labels could not be serialized
+    ExportError,
+    /// The labels file could not be written
+    FileWriteError,
+    /// The labels could not be encrypted or decrypted
+    EncryptionError,
+    /// The passphrase can't decrypt the labels
+    WrongPassphrase,
+    /// The record index is past the end of the labels
+    IndexOutOfRange,
+}
+
+/// Options for parsing BIP329 JSONL
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParseOptions {
+    /// Skip records whose `type` is not supported, instead of failing
+    pub ignore_unknown_types: bool,
+}
+
+/// The BIP329 `type` of a record
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabelType {
+    Transaction,
+    Address,
+    PublicKey,
+    Input,
+    Output,
+    ExtendedPublicKey,
+    SilentPaymentsScan,
+    /// Returned by `bip329_record_type()` for a null record
+    Invalid = -1,
+}
+
+/// A copy of a single label record, with its fields as C strings
+pub struct Record {
+    label_type: LabelType,
+    ref_: CString,
+    label: Option<CString>,
+    origin: Option<CString>,
+    spendable: c_int,
+}
+
+struct Error {
+    code: ErrorCode,
+    message: String,
+}
+
+impl Error {
+    fn new(code: ErrorCode, message: impl Display) -> Self {
+        let message = message.to_string();
+        Self { code, message }
+    }
+
+    fn null_pointer(argument: &str) -> Self {
+        Self::new(ErrorCode::NullPointer, format!("`{argument}` is null"))
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        match error {
+            ParseError::FileReadError(_) => Self::new(ErrorCode::FileReadError, error),
+            error => Self::new(ErrorCode::ParseError, error),
+        }
+    }
+}
+
+impl From<ExportError> for Error {
+    fn from(error: ExportError) -> Self {
+        match error {
+            ExportError::FileWriteError(_) => Self::new(ErrorCode::FileWriteError, error),
+            error => Self::new(ErrorCode::ExportError, error),
+        }
+    }
+}
+
+impl From<EncryptionError> for Error {
+    fn from(error: EncryptionError) -> Self {
+        match error {
+            EncryptionError::ParseError(error) => error.into(),
+            EncryptionError::WrongPassphrase => Self::new(ErrorCode::WrongPassphrase, error),
+            error => Self::new(ErrorCode::EncryptionError, error),
+        }
+    }
+}
+
+impl Record {
+    fn new(label: &Label) -> Result<Self, Error> {
+        let label_type = match label {
+            Label::Transaction(_) => LabelType::Transaction,
+            Label::Address(_) => LabelType::Address,
+            Label::PublicKey(_) => LabelType::PublicKey,
+            Label::Input(_) => LabelType::Input,
+            Label::Output(_) => LabelType::Output,
+            Label::ExtendedPublicKey(_) => LabelType::ExtendedPublicKey,
+            Label::SilentPaymentsScan(_) => LabelType::SilentPaymentsScan,
+        };
+
+        let origin = match label {
+            Label::Transaction(record) => record.origin.clone(),
+            _ => None,
+        };
+
+        let spendable = match label {
+            Label::Output(record) => c_int::from(record.spendable()),
+            _ => -1,
+        };
+
+        Ok(Self {
+            label_type,
+            ref_: c_string(label.ref_().to_string())?,
+            label: label
+                .label()
+                .map(str::to_string)
+                .map(c_string)
+                .transpose()?,
+            origin: origin.map(c_string).transpose()?,
+            spendable,
+        })
+    }
+}
+
+/// Run a fallible call, saving the error message for `bip329_last_error_message()`
+fn run(call: impl FnOnce() -> Result<(), Error>) -> ErrorCode {
+    let Err(error) = call() else {
+        return ErrorCode::Ok;
+    };
+
+    let message = CString::new(error.message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+
+    error.code
+}
+
+fn c_string(string: String) -> Result<CString, Error> {
+    CString::new(string).map_err(|error| Error::new(ErrorCode::InvalidString, error))
+}
+
+/// # Safety
+///
+/// `string` must be null or a valid NUL-terminated string
+unsafe fn str_arg<'a>(string: *const c_char, argument: &str) -> Result<&'a str, Error> {
+    if string.is_null() {
+        return Err(Error::null_pointer(argument));
+    }
+
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|error| Error::new(ErrorCode::InvalidString, format!("`{argument}`: {error}")))
+}
+
+/// # Safety
+///
+/// `labels` must be null or a handle from this library that has not been freed
+unsafe fn labels_arg<'a>(labels: *const Labels) -> Result<&'a Labels, Error> {
+    labels.as_ref().ok_or_else(|| Error::null_pointer("labels"))
+}
+
+/// Box the value and write the owning pointer to `out`, the value is dropped if `out`
+/// is null
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes
+unsafe fn write_out<T>(out: *mut *mut T, value: T) -> Result<(), Error> {
+    if out.is_null() {
+        return Err(Error::null_pointer("out"));
+    }
+
+    out.write(Box::into_raw(Box::new(value)));
+    Ok(())
+}
+
+/// Write the owning pointer of the string to `out`, the string is dropped if `out` is
+/// null
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes
+unsafe fn write_string(out: *mut *mut c_char, string: CString) -> Result<(), Error> {
+    if out.is_null() {
+        return Err(Error::null_pointer("out"));
+    }
+
+    out.write(string.into_raw());
+    Ok(())
+}
+
+/// # Safety
+///
+/// `options` must be null or point to valid options
+unsafe fn parse_options(options: *const ParseOptions) -> LabelParseOptions {
+    let options = options.as_ref().copied().unwrap_or_default();
+    LabelParseOptions::default().ignore_unknown_types(options.ignore_unknown_types)
+}
+
+/// The message of the last failed call on this thread, or null if no call has failed.
+///
+/// The string is owned by the library and valid until the next failed call on this
+/// thread.
+#[no_mangle]
+pub extern "C" fn bip329_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Parse labels from a JSONL string, `options` may be null for the defaults.
+///
+/// On success `*out` is set to a handle that must be freed with `bip329_labels_free()`.
+///
+/// # Safety
+///
+/// `jsonl` must be a NUL-terminated string, `options` null or valid, and `out` valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_parse(
+    jsonl: *const c_char,
+    options: *const ParseOptions,
+    out: *mut *mut Labels,
+) -> ErrorCode {
+    run(|| {
+        let jsonl = str_arg(jsonl, "jsonl")?;
+        let labels = Labels::try_from_str_with_options(jsonl, parse_options(options))?;
+        write_out(out, labels)
+    })
+}
+
+/// Parse labels from a JSONL file, `options` may be null for the defaults.
+///
+/// On success `*out` is set to a handle that must be freed with `bip329_labels_free()`.
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string, `options` null or valid, and `out` valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_parse_file(
+    path: *const c_char,
+    options: *const ParseOptions,
+    out: *mut *mut Labels,
+) -> ErrorCode {
+    run(|| {
+        let path = str_arg(path, "path")?;
+        let labels = Labels::try_from_file_with_options(path, parse_options(options))?;
+        write_out(out, labels)
+    })
+}
+
+/// Free labels, does nothing if `labels` is null.
+///
+/// # Safety
+///
+/// `labels` must be null or a handle from this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_free(labels: *mut Labels) {
+    if !labels.is_null() {
+        drop(Box::from_raw(labels));
+    }
+}
+
+/// The number of records, or 0 if `labels` is null.
+///
+/// # Safety
+///
+/// `labels` must be null or a handle from this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_len(labels: *const Labels) -> usize {
+    labels.as_ref().map_or(0, |labels| labels.len())
+}
+
+/// Copy the record at `index`.
+///
+/// On success `*out` is set to a record that must be freed with `bip329_record_free()`.
+///
+/// # Safety
+///
+/// `labels` must be a handle from this library that has not been freed, and `out`
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_get(
+    labels: *const Labels,
+    index: usize,
+    out: *mut *mut Record,
+) -> ErrorCode {
+    run(|| {
+        let labels = labels_arg(labels)?;
+        let label = labels.get(index).ok_or_else(|| {
+            let message = format!("Index {index} is out of range for {} labels", labels.len());
+            Error::new(ErrorCode::IndexOutOfRange, message)
+        })?;
+
+        write_out(out, Record::new(label)?)
+    })
+}
+
+/// Export the labels to a JSONL string.
+///
+/// On success `*out` is set to a string that must be freed with `bip329_string_free()`.
+///
+/// # Safety
+///
+/// `labels` must be a handle from this library that has not been freed, and `out`
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_export(
+    labels: *const Labels,
+    out: *mut *mut c_char,
+) -> ErrorCode {
+    run(|| {
+        let jsonl = c_string(labels_arg(labels)?.export()?)?;
+        write_string(out, jsonl)
+    })
+}
+
+/// Export the labels to a JSONL file.
+///
+/// # Safety
+///
+/// `labels` must be a handle from this library that has not been freed, and `path` a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_export_to_file(
+    labels: *const Labels,
+    path: *const c_char,
+) -> ErrorCode {
+    run(|| {
+        let path = str_arg(path, "path")?;
+        Ok(labels_arg(labels)?.export_to_file(path)?)
+    })
+}
+
+/// Encrypt the labels with a passphrase, as ASCII armored age.
+///
+/// On success `*out` is set to a string that must be freed with `bip329_string_free()`.
+///
+/// # Safety
+///
+/// `labels` must be a handle from this library that has not been freed, `passphrase`
+/// a NUL-terminated string, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_encrypt(
+    labels: *const Labels,
+    passphrase: *const c_char,
+    out: *mut *mut c_char,
+) -> ErrorCode {
+    run(|| {
+        let passphrase = str_arg(passphrase, "passphrase")?;
+        let encrypted = EncryptedLabels::encrypt(labels_arg(labels)?, passphrase)?;
+        let armor = c_string(encrypted.to_armor()?)?;
+        write_string(out, armor)
+    })
+}
+
+/// Encrypt the labels with a passphrase, and write them to a binary age file.
+///
+/// # Safety
+///
+/// `labels` must be a handle from this library that has not been freed, and
+/// `passphrase` and `path` NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_encrypt_to_file(
+    labels: *const Labels,
+    passphrase: *const c_char,
+    path: *const c_char,
+) -> ErrorCode {
+    run(|| {
+        let passphrase = str_arg(passphrase, "passphrase")?;
+        let path = str_arg(path, "path")?;
+        let encrypted = EncryptedLabels::encrypt(labels_arg(labels)?, passphrase)?;
+        Ok(encrypted.write_to_file(path)?)
+    })
+}
+
+/// Decrypt labels from binary, ASCII armored or hex encoded age bytes.
+///
+/// On success `*out` is set to a handle that must be freed with `bip329_labels_free()`.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes, `passphrase` a NUL-terminated string,
+/// and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_decrypt(
+    data: *const u8,
+    len: usize,
+    passphrase: *const c_char,
+    out: *mut *mut Labels,
+) -> ErrorCode {
+    run(|| {
+        if data.is_null() {
+            return Err(Error::null_pointer("data"));
+        }
+
+        let passphrase = str_arg(passphrase, "passphrase")?;
+        let encoded = slice::from_raw_parts(data, len).to_vec();
+        let labels = EncryptedLabels::from_encoded(encoded)?.decrypt(passphrase)?;
+        write_out(out, labels)
+    })
+}
+
+/// Decrypt labels from a binary, ASCII armored or hex encoded age file.
+///
+/// On success `*out` is set to a handle that must be freed with `bip329_labels_free()`.
+///
+/// # Safety
+///
+/// `path` and `passphrase` must be NUL-terminated strings, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_labels_decrypt_file(
+    path: *const c_char,
+    passphrase: *const c_char,
+    out: *mut *mut Labels,
+) -> ErrorCode {
+    run(|| {
+        let path = str_arg(path, "path")?;
+        let passphrase = str_arg(passphrase, "passphrase")?;
+        let labels = EncryptedLabels::read_from_file(path)?.decrypt(passphrase)?;
+        write_out(out, labels)
+    })
+}
+
+/// The BIP329 `type` of the record, or `BIP329_LABEL_TYPE_INVALID` if `record` is null.
+///
+/// # Safety
+///
+/// `record` must be null or a record from this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_record_type(record: *const Record) -> LabelType {
+    record
+        .as_ref()
+        .map_or(LabelType::Invalid, |record| record.label_type)
+}
+
+/// The BIP329 `ref` of the record, owned by the record, or null if `record` is null.
+///
+/// # Safety
+///
+/// `record` must be null or a record from this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_record_ref(record: *const Record) -> *const c_char {
+    record
+        .as_ref()
+        .map_or(ptr::null(), |record| record.ref_.as_ptr())
+}
+
+/// The label of the record, owned by the record, or null if it has no label or
+/// `record` is null.
+///
+/// # Safety
+///
+/// `record` must be null or a record from this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_record_label(record: *const Record) -> *const c_char {
+    record
+        .as_ref()
+        .and_then(|record| record.label.as_deref())
+        .map_or(ptr::null(), CStr::as_ptr)
+}
+
+/// The origin descriptor of a transaction record, owned by the record, or null.
+///
+/// # Safety
+///
+/// `record` must be null or a record from this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_record_origin(record: *const Record) -> *const c_char {
+    record
+        .as_ref()
+        .and_then(|record| record.origin.as_deref())
+        .map_or(ptr::null(), CStr::as_ptr)
+}
+
+/// 1 if an output record is spendable, which is the default, 0 if it is not, and -1
+/// for other records or if `record` is null.
+///
+/// # Safety
+///
+/// `record` must be null or a record from this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_record_spendable(record: *const Record) -> c_int {
+    record.as_ref().map_or(-1, |record| record.spendable)
+}
+
+/// Free a record, does nothing if `record` is null.
+///
+/// # Safety
+///
+/// `record` must be null or a record from this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_record_free(record: *mut Record) {
+    if !record.is_null() {
+        drop(Box::from_raw(record));
+    }
+}
+
+/// Free a string returned by the library, does nothing if `string` is null.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by the library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bip329_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
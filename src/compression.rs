@@ -0,0 +1,222 @@
+//! Module for reading and writing compressed label files.
+//!
+//! Requires the `gzip` and/or `zstd` feature. Compressed input is detected by its
+//! magic bytes, so [`Labels::try_from_file`] reads plain and compressed files alike.
+
+use std::{
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{error::ExportError, Labels};
+
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// The largest decompressed label file that is read, in bytes
+///
+/// Reading fails past it, so a small compressed file can't expand without bound.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// The compression format of a label file.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip, requires the `gzip` feature
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, requires the `zstd` feature
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression format from the first bytes of the input.
+    ///
+    /// Returns `None` for uncompressed input or for formats whose feature is disabled.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        #[cfg(feature = "gzip")]
+        if bytes.starts_with(&GZIP_MAGIC) {
+            return Some(Self::Gzip);
+        }
+
+        #[cfg(feature = "zstd")]
+        if bytes.starts_with(&ZSTD_MAGIC) {
+            return Some(Self::Zstd);
+        }
+
+        None
+    }
+}
+
+impl Labels {
+    /// Export the Labels struct to a compressed file.
+    pub fn export_to_file_with_compression(
+        &self,
+        path: impl AsRef<Path>,
+        compression: Compression,
+    ) -> Result<(), ExportError> {
+        let file = std::fs::File::create(path)?;
+        export_compressed(self, compression, BufWriter::new(file))?.flush()?;
+        Ok(())
+    }
+
+    /// Export the Labels struct to a writer, compressing them as they are written.
+    ///
+    /// Returns the writer once the compressed stream is finished.
+    pub fn export_to_writer_with_compression<W: Write>(
+        &self,
+        writer: W,
+        compression: Compression,
+    ) -> Result<W, ExportError> {
+        export_compressed(self, compression, writer)
+    }
+}
+
+/// Export the labels to the writer, compressing them as they are written
+///
+/// Returns the inner writer once the compressed stream is finished
+pub(crate) fn export_compressed<W: Write>(
+    labels: &Labels,
+    compression: Compression,
+    writer: W,
+) -> Result<W, ExportError> {
+    match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+            labels.export_to_writer(&mut encoder)?;
+            Ok(encoder.finish()?)
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => {
+            let mut encoder = zstd::Encoder::new(writer, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+            labels.export_to_writer(&mut encoder)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
+
+/// Wrap the reader in a decoder when its contents start with a known magic number
+///
+/// Reading fails once more than [`MAX_DECOMPRESSED_SIZE`] bytes are decompressed.
+pub(crate) fn decompressing_reader<'a, R>(reader: R) -> io::Result<Box<dyn BufRead + 'a>>
+where
+    R: BufRead + 'a,
+{
+    decompressing_reader_with_limit(reader, MAX_DECOMPRESSED_SIZE)
+}
+
+fn decompressing_reader_with_limit<'a, R>(
+    mut reader: R,
+    limit: u64,
+) -> io::Result<Box<dyn BufRead + 'a>>
+where
+    R: BufRead + 'a,
+{
+    let Some(compression) = Compression::detect(reader.fill_buf()?) else {
+        return Ok(Box::new(reader));
+    };
+
+    let decoder: Box<dyn Read + 'a> = match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+    };
+
+    // one byte past the limit, to tell a file of exactly the limit from a larger one
+    let limited = Limited {
+        reader: decoder.take(limit + 1),
+        limit,
+    };
+
+    Ok(Box::new(BufReader::new(limited)))
+}
+
+/// A reader that fails once more than the limit is read, where [`Read::take`] would
+/// silently stop
+struct Limited<R> {
+    reader: io::Take<R>,
+    limit: u64,
+}
+
+impl<R: Read> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+
+        if read > 0 && self.reader.limit() == 0 {
+            let message = format!("decompressed labels are larger than {} bytes", self.limit);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn loop_back(compression: Compression) {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let file = tempfile::NamedTempFile::new().unwrap();
+
+        labels
+            .export_to_file_with_compression(file.path(), compression)
+            .unwrap();
+
+        let bytes = std::fs::read(file.path()).unwrap();
+        assert_eq!(Compression::detect(&bytes), Some(compression));
+        assert!(bytes.len() < labels.export().unwrap().len());
+
+        let decompressed = Labels::try_from_file(file.path()).unwrap();
+        assert_eq!(labels, decompressed);
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn loop_back_gzip() {
+        loop_back(Compression::Gzip);
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn loop_back_zstd() {
+        loop_back(Compression::Zstd);
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn decompression_is_limited() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let exported = labels.export().unwrap();
+        let compressed = labels
+            .export_to_writer_with_compression(Vec::new(), Compression::Gzip)
+            .unwrap();
+
+        let read = |limit: u64| {
+            let mut reader = decompressing_reader_with_limit(compressed.as_slice(), limit)?;
+            let mut decompressed = String::new();
+            reader
+                .read_to_string(&mut decompressed)
+                .map(|_| decompressed)
+        };
+
+        let size = exported.len() as u64 + 1;
+        assert_eq!(read(size).unwrap().trim_end(), exported);
+
+        let error = read(size - 1).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn uncompressed_input_is_not_detected() {
+        let bytes = std::fs::read("tests/data/labels.jsonl").unwrap();
+        assert_eq!(Compression::detect(&bytes), None);
+    }
+}
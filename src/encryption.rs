@@ -1,33 +1,250 @@
 //! Module for encrypting and decrypting labels.
 
-use std::{io::Write as _, path::Path};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    iter, mem,
+    path::Path,
+};
 
-use age::secrecy::SecretString;
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    secrecy::SecretString,
+    Identity, Recipient,
+};
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
 
-use crate::{error::EncryptionError, Labels};
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use crate::compression::{self, Compression};
+use crate::{
+    error::{EncryptionError, ExportError},
+    Labels,
+};
+
+/// Re-export of the [`age`] crate, for generating keys and implementing custom
+/// recipients and identities
+pub use age;
+
+pub mod record;
+pub mod wallet;
+
+mod header;
+mod stream;
+
+pub use self::header::{LabelsHeader, HEADER_VERSION};
+
+pub use self::stream::LabelReader;
+
+const ARMOR_BEGIN: &[u8] = b"-----BEGIN AGE ENCRYPTED FILE-----";
+const BINARY_BEGIN: &[u8] = b"age-encryption.org/";
 
 /// A list of encrypted labels.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Object), uniffi::export(Debug, Eq))]
 pub struct EncryptedLabels(Vec<u8>);
 
+/// Options for passphrase encryption and decryption
+///
+/// Work factors are the base 2 logarithm of the scrypt cost parameter `N`, between
+/// 1 and 63. When unset, age picks a work factor that takes about one second on the
+/// current device, and accepts up to 16 times that when decrypting.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct PassphraseOptions {
+    pub(crate) work_factor: Option<u8>,
+    pub(crate) max_work_factor: Option<u8>,
+}
+
+impl PassphraseOptions {
+    /// Set the scrypt work factor used when encrypting
+    ///
+    /// Lower values make encryption and decryption faster on low-end devices, at the
+    /// cost of making passphrase guessing cheaper.
+    #[must_use]
+    pub fn work_factor(mut self, log_n: u8) -> Self {
+        self.work_factor = Some(log_n);
+        self
+    }
+
+    /// Set the maximum scrypt work factor accepted when decrypting
+    ///
+    /// Files requiring more work are rejected before any key derivation, so a hostile
+    /// file can't demand an unbounded amount of work.
+    #[must_use]
+    pub fn max_work_factor(mut self, max_log_n: u8) -> Self {
+        self.max_work_factor = Some(max_log_n);
+        self
+    }
+
+    fn recipient(&self, passphrase: &str) -> Result<age::scrypt::Recipient, EncryptionError> {
+        let mut recipient = age::scrypt::Recipient::new(SecretString::new(passphrase.into()));
+        if let Some(work_factor) = self.work_factor {
+            recipient.set_work_factor(validate_work_factor(work_factor)?);
+        }
+
+        Ok(recipient)
+    }
+
+    pub(crate) fn identity(
+        &self,
+        passphrase: &str,
+    ) -> Result<age::scrypt::Identity, EncryptionError> {
+        let mut identity = age::scrypt::Identity::new(SecretString::new(passphrase.into()));
+        if let Some(max_work_factor) = self.max_work_factor {
+            identity.set_max_work_factor(validate_work_factor(max_work_factor)?);
+        }
+
+        Ok(identity)
+    }
+
+    pub(crate) fn encryptor(&self, passphrase: &str) -> Result<age::Encryptor, EncryptionError> {
+        let recipient = self.recipient(passphrase)?;
+        Ok(age::Encryptor::with_recipients(iter::once(
+            &recipient as _,
+        ))?)
+    }
+}
+
+/// Map an error decrypting the file key with a passphrase.
+///
+/// age fails to decrypt the scrypt stanza's file key with a wrong passphrase, and finds
+/// no matching key when the labels were encrypted to recipients instead.
+fn passphrase_error(error: age::DecryptError) -> EncryptionError {
+    match error {
+        age::DecryptError::DecryptionFailed
+        | age::DecryptError::KeyDecryptionFailed
+        | age::DecryptError::NoMatchingKeys => EncryptionError::WrongPassphrase,
+        error => error.into(),
+    }
+}
+
+fn validate_work_factor(log_n: u8) -> Result<u8, EncryptionError> {
+    match log_n {
+        1..=63 => Ok(log_n),
+        _ => Err(EncryptionError::InvalidWorkFactor(log_n)),
+    }
+}
+
 impl EncryptedLabels {
     /// Encrypt the Labels struct using the given passphrase.
     pub fn encrypt(labels: &Labels, passphrase: &str) -> Result<Self, EncryptionError> {
-        let labels = labels.export()?;
+        Self::encrypt_with_options(labels, passphrase, PassphraseOptions::default())
+    }
+
+    /// Encrypt the Labels struct using the given passphrase and scrypt options.
+    pub fn encrypt_with_options(
+        labels: &Labels,
+        passphrase: &str,
+        options: PassphraseOptions,
+    ) -> Result<Self, EncryptionError> {
+        Self::encrypt_with(options.encryptor(passphrase)?, None, |writer| {
+            labels.export_to_writer(writer)
+        })
+    }
+
+    /// Encrypt the Labels struct using the given passphrase, with a plaintext header.
+    ///
+    /// The header can be read with [`EncryptedLabels::header`] before decrypting, see
+    /// [`LabelsHeader`] for how it is authenticated.
+    pub fn encrypt_with_header(
+        labels: &Labels,
+        passphrase: &str,
+        header: &LabelsHeader,
+    ) -> Result<Self, EncryptionError> {
+        let options = PassphraseOptions::default();
+        Self::encrypt_with_header_and_options(labels, passphrase, header, options)
+    }
+
+    /// Encrypt the Labels struct using the given passphrase and scrypt options, with a
+    /// plaintext header.
+    pub fn encrypt_with_header_and_options(
+        labels: &Labels,
+        passphrase: &str,
+        header: &LabelsHeader,
+        options: PassphraseOptions,
+    ) -> Result<Self, EncryptionError> {
+        Self::encrypt_with(options.encryptor(passphrase)?, Some(header), |writer| {
+            labels.export_to_writer(writer)
+        })
+    }
 
-        let encrypted = {
-            let encryptor =
-                age::Encryptor::with_user_passphrase(SecretString::new(passphrase.into()));
+    /// Compress the exported labels, then encrypt them using the given passphrase.
+    ///
+    /// [`EncryptedLabels::decrypt`] detects and decompresses the plaintext.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn encrypt_with_compression(
+        labels: &Labels,
+        passphrase: &str,
+        compression: Compression,
+    ) -> Result<Self, EncryptionError> {
+        let options = PassphraseOptions::default();
+        Self::encrypt_with_compression_and_options(labels, passphrase, compression, options)
+    }
 
-            let mut encrypted = vec![];
-            let mut writer = encryptor.wrap_output(&mut encrypted)?;
+    /// Compress the exported labels, then encrypt them using the given passphrase and
+    /// scrypt options.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn encrypt_with_compression_and_options(
+        labels: &Labels,
+        passphrase: &str,
+        compression: Compression,
+        options: PassphraseOptions,
+    ) -> Result<Self, EncryptionError> {
+        Self::encrypt_with(options.encryptor(passphrase)?, None, |writer| {
+            compression::export_compressed(labels, compression, writer).map(drop)
+        })
+    }
 
-            writer.write_all(labels.as_bytes())?;
-            writer.finish()?;
+    /// Encrypt the Labels struct to one or more age recipients.
+    ///
+    /// Any of the matching identities can decrypt the labels with
+    /// [`EncryptedLabels::decrypt_with_identities`], no shared passphrase is needed.
+    /// Use [`parse_recipient`] to parse `age1...` and SSH public keys.
+    pub fn encrypt_to_recipients(
+        labels: &Labels,
+        recipients: &[Box<dyn Recipient + Send>],
+    ) -> Result<Self, EncryptionError> {
+        let recipients = recipients.iter().map(|recipient| recipient.as_ref() as _);
+        let encryptor = age::Encryptor::with_recipients(recipients)?;
 
-            encrypted
-        };
+        Self::encrypt_with(encryptor, None, |writer| labels.export_to_writer(writer))
+    }
+
+    /// Encrypt the Labels struct to one or more age recipients, with a plaintext header.
+    ///
+    /// See [`EncryptedLabels::encrypt_to_recipients`] and [`LabelsHeader`].
+    pub fn encrypt_to_recipients_with_header(
+        labels: &Labels,
+        recipients: &[Box<dyn Recipient + Send>],
+        header: &LabelsHeader,
+    ) -> Result<Self, EncryptionError> {
+        let recipients = recipients.iter().map(|recipient| recipient.as_ref() as _);
+        let encryptor = age::Encryptor::with_recipients(recipients)?;
+
+        Self::encrypt_with(encryptor, Some(header), |writer| {
+            labels.export_to_writer(writer)
+        })
+    }
+
+    /// Encrypt the plaintext written by `export`
+    ///
+    /// The plaintext is streamed into the encryptor, so it is never buffered in memory
+    fn encrypt_with(
+        encryptor: age::Encryptor,
+        header: Option<&LabelsHeader>,
+        export: impl FnOnce(&mut dyn Write) -> Result<(), ExportError>,
+    ) -> Result<Self, EncryptionError> {
+        let prefix = header.map(LabelsHeader::to_prefix).transpose()?;
+
+        let mut encrypted = prefix.clone().unwrap_or_default();
+        let mut writer = encryptor.wrap_output(&mut encrypted)?;
+
+        // the encrypted copy of the header authenticates the plaintext one
+        if let Some(prefix) = prefix {
+            writer.write_all(&prefix[header::MAGIC.len()..])?;
+        }
+
+        export(&mut writer)?;
+        writer.finish()?;
 
         Ok(Self(encrypted))
     }
@@ -38,12 +255,53 @@ impl EncryptedLabels {
         Ok(Self(encrypted))
     }
 
+    /// Create a new EncryptedLabels struct from an ASCII armored string.
+    pub fn from_armor(armor: &str) -> Result<Self, EncryptionError> {
+        let mut encrypted = vec![];
+        ArmoredReader::new(armor.trim().as_bytes())
+            .read_to_end(&mut encrypted)
+            .map_err(EncryptionError::ArmorError)?;
+
+        Ok(Self(encrypted))
+    }
+
+    /// Create a new EncryptedLabels struct from binary, ASCII armored or hex encoded bytes.
+    ///
+    /// The encoding is detected from the contents.
+    pub fn from_encoded(encoded: Vec<u8>) -> Result<Self, EncryptionError> {
+        let trimmed = encoded.trim_ascii();
+
+        if trimmed.starts_with(ARMOR_BEGIN) {
+            let armor = std::str::from_utf8(trimmed)
+                .map_err(|error| EncryptionError::ArmorError(std::io::Error::other(error)))?;
+            return Self::from_armor(armor);
+        }
+
+        let is_hex = !trimmed.is_empty() && trimmed.iter().all(u8::is_ascii_hexdigit);
+        if !encoded.starts_with(BINARY_BEGIN) && is_hex {
+            return Ok(Self(hex::decode(trimmed)?));
+        }
+
+        Ok(Self(encoded))
+    }
+
     /// Create a new EncryptedLabels struct from a file.
+    ///
+    /// Binary, ASCII armored and hex encoded files are detected automatically.
     pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self, EncryptionError> {
         let path = path.as_ref();
         let encrypted = std::fs::read(path)?;
 
-        Ok(Self(encrypted))
+        Self::from_encoded(encrypted)
+    }
+
+    /// Read the plaintext header, without decrypting the labels.
+    ///
+    /// Returns `None` when the labels were encrypted without a header. The header is
+    /// only authenticated once the labels are decrypted.
+    pub fn header(&self) -> Result<Option<LabelsHeader>, EncryptionError> {
+        let (prefix, _) = header::split(&self.0)?;
+        header::parse(prefix)
     }
 
     /// Get the encrypted bytes of the EncryptedLabels struct.
@@ -51,17 +309,121 @@ impl EncryptedLabels {
         self.0
     }
 
+    /// Get a reference to the encrypted bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Whether the bytes are an age file, with or without a plaintext header.
+    ///
+    /// Only the start of the file is checked, it may still be truncated or corrupted.
+    #[cfg(feature = "server")]
+    pub(crate) fn is_age_file(&self) -> bool {
+        header::split(&self.0).is_ok_and(|(_, age)| age.starts_with(BINARY_BEGIN))
+    }
+
     /// Decrypt the EncryptedLabels struct using the given passphrase.
     pub fn decrypt(&self, passphrase: &str) -> Result<Labels, EncryptionError> {
-        let encrypted = &self.0;
+        self.decrypt_with_options(passphrase, PassphraseOptions::default())
+    }
+
+    /// Decrypt the EncryptedLabels struct using the given passphrase and scrypt options.
+    pub fn decrypt_with_options(
+        &self,
+        passphrase: &str,
+        options: PassphraseOptions,
+    ) -> Result<Labels, EncryptionError> {
+        let identity = options.identity(passphrase)?;
+        self.decrypt_with(iter::once(&identity as _), passphrase_error)
+    }
+
+    /// Re-encrypt the labels under a new passphrase.
+    ///
+    /// The plaintext is streamed from the decryptor into the encryptor, it is never
+    /// parsed, fully buffered or written to disk.
+    pub fn rekey(
+        &self,
+        old_passphrase: &str,
+        new_passphrase: &str,
+    ) -> Result<Self, EncryptionError> {
+        self.rekey_with_options(old_passphrase, new_passphrase, PassphraseOptions::default())
+    }
+
+    /// Re-encrypt the labels under a new passphrase using the given scrypt options.
+    ///
+    /// The maximum work factor applies to decrypting with the old passphrase, the work
+    /// factor to encrypting with the new one.
+    pub fn rekey_with_options(
+        &self,
+        old_passphrase: &str,
+        new_passphrase: &str,
+        options: PassphraseOptions,
+    ) -> Result<Self, EncryptionError> {
+        let identity = options.identity(old_passphrase)?;
+        let (prefix, ciphertext) = header::split(&self.0)?;
+        let decryptor = age::Decryptor::new_buffered(ciphertext)?;
+        let mut reader = decryptor
+            .decrypt(iter::once(&identity as _))
+            .map_err(passphrase_error)?;
 
-        let decrypted = {
-            let secret_string = SecretString::new(passphrase.into());
-            let identity = age::scrypt::Identity::new(secret_string);
-            age::decrypt(&identity, encrypted)?
+        // the header and its encrypted copy are kept as they are
+        let mut encrypted = prefix.to_vec();
+        let mut writer = options
+            .encryptor(new_passphrase)?
+            .wrap_output(&mut encrypted)?;
+
+        // writing to a Vec can't fail, so any error is from the ciphertext
+        copy_zeroizing(&mut reader, &mut writer).map_err(EncryptionError::CorruptedCiphertext)?;
+        writer.finish()?;
+
+        Ok(Self(encrypted))
+    }
+
+    /// Decrypt labels that were encrypted to recipients, using any matching identity.
+    ///
+    /// Use [`parse_identities`] or [`read_identities_file`] to load identities.
+    pub fn decrypt_with_identities(
+        &self,
+        identities: &[Box<dyn Identity>],
+    ) -> Result<Labels, EncryptionError> {
+        let identities = identities.iter().map(|identity| identity.as_ref());
+        self.decrypt_with(identities, EncryptionError::from)
+    }
+
+    /// Decrypt with the identities, `map_error` maps the errors decrypting the file key
+    fn decrypt_with<'a>(
+        &self,
+        identities: impl Iterator<Item = &'a dyn Identity>,
+        map_error: fn(age::DecryptError) -> EncryptionError,
+    ) -> Result<Labels, EncryptionError> {
+        let (prefix, encrypted) = header::split(&self.0)?;
+        header::parse(prefix)?;
+
+        let mut decrypted = {
+            let decryptor = age::Decryptor::new_buffered(encrypted)?;
+            let reader = decryptor.decrypt(identities).map_err(map_error)?;
+
+            // the plaintext is never longer than the ciphertext
+            read_to_end_zeroizing(reader, encrypted.len())
+                .map_err(EncryptionError::CorruptedCiphertext)?
         };
 
-        let labels_string = String::from_utf8(decrypted)?;
+        let header_len = decrypted.len() - header::strip(prefix, &decrypted)?.len();
+        decrypted.drain(..header_len);
+
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        let mut decrypted = compression::decompressing_reader(decrypted.as_slice())
+            .and_then(|reader| read_to_end_zeroizing(reader, decrypted.len()))
+            .map_err(EncryptionError::CompressionError)?;
+
+        #[cfg(not(any(feature = "gzip", feature = "zstd")))]
+        let mut decrypted = decrypted;
+
+        // checked first, a `FromUtf8Error` would keep a copy of the plaintext
+        std::str::from_utf8(&decrypted)?;
+        let labels_string = Zeroizing::new(
+            String::from_utf8(mem::take(&mut *decrypted)).expect("checked to be valid UTF-8"),
+        );
         let labels = Labels::try_from_str(&labels_string)?;
 
         Ok(labels)
@@ -75,6 +437,21 @@ impl EncryptedLabels {
         Ok(hex_encoded)
     }
 
+    /// Export the EncryptedLabels struct to an ASCII armored string.
+    ///
+    /// The armor is the standard age PEM-like format, wrapped at 64 columns.
+    pub fn to_armor(&self) -> Result<String, EncryptionError> {
+        let mut writer = ArmoredWriter::wrap_output(vec![], Format::AsciiArmor)
+            .map_err(EncryptionError::ArmorError)?;
+
+        writer
+            .write_all(&self.0)
+            .map_err(EncryptionError::ArmorError)?;
+        let armored = writer.finish().map_err(EncryptionError::ArmorError)?;
+
+        Ok(String::from_utf8(armored).expect("ASCII armor is valid UTF-8"))
+    }
+
     /// Export the EncryptedLabels struct to a file.
     pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<(), EncryptionError> {
         let path = path.as_ref();
@@ -87,6 +464,138 @@ impl EncryptedLabels {
     }
 }
 
+/// Parse an age recipient from its string encoding.
+///
+/// Accepts native `age1...` recipients, and with the `ssh` feature, `ssh-ed25519`
+/// and `ssh-rsa` public keys.
+pub fn parse_recipient(recipient: &str) -> Result<Box<dyn Recipient + Send>, EncryptionError> {
+    let recipient = recipient.trim();
+
+    if let Ok(recipient) = recipient.parse::<age::x25519::Recipient>() {
+        return Ok(Box::new(recipient));
+    }
+
+    #[cfg(feature = "ssh")]
+    if let Ok(recipient) = recipient.parse::<age::ssh::Recipient>() {
+        return Ok(Box::new(recipient));
+    }
+
+    Err(EncryptionError::InvalidRecipient)
+}
+
+/// Parse identities from the contents of an identity file.
+///
+/// Accepts age identity files, with one `AGE-SECRET-KEY-1...` per line and `#`
+/// comments, and with the `ssh` feature, unencrypted OpenSSH private keys.
+pub fn parse_identities(identities: &str) -> Result<Vec<Box<dyn Identity>>, EncryptionError> {
+    #[cfg(feature = "ssh")]
+    if identities.trim_start().starts_with("-----BEGIN") {
+        let identity = age::ssh::Identity::from_buffer(identities.as_bytes(), None)
+            .map_err(EncryptionError::InvalidIdentity)?;
+
+        let age::ssh::Identity::Unencrypted(_) = identity else {
+            let error = std::io::Error::other("encrypted or unsupported SSH identity");
+            return Err(EncryptionError::InvalidIdentity(error));
+        };
+
+        return Ok(vec![Box::new(identity)]);
+    }
+
+    let identities = age::IdentityFile::from_buffer(BufReader::new(identities.as_bytes()))
+        .map_err(EncryptionError::InvalidIdentity)?
+        .into_identities()?;
+
+    Ok(identities)
+}
+
+/// Read identities from an identity file, see [`parse_identities`].
+pub fn read_identities_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<Box<dyn Identity>>, EncryptionError> {
+    let identities = std::fs::read_to_string(path)?;
+    parse_identities(&identities)
+}
+
+/// Size of the intermediate buffers used when reading plaintext
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Read the reader to the end into a buffer that is zeroized when dropped
+///
+/// `Vec` growth would leave stale copies of the plaintext in freed memory, so the
+/// buffer is grown by hand, zeroizing each outgrown allocation.
+fn read_to_end_zeroizing(mut reader: impl Read, capacity: usize) -> io::Result<Zeroizing<Vec<u8>>> {
+    let mut buffer = Zeroizing::new(Vec::with_capacity(capacity));
+    let mut chunk = Zeroizing::new([0; CHUNK_SIZE]);
+
+    loop {
+        let read = match reader.read(chunk.as_mut_slice()) {
+            Ok(0) => return Ok(buffer),
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+
+        extend_zeroizing(&mut buffer, &chunk[..read]);
+    }
+}
+
+/// Read a line, including its newline, into a buffer that is zeroized when dropped
+///
+/// Like [`BufRead::read_until`], but grows the buffer as [`read_to_end_zeroizing`] does.
+fn read_line_zeroizing(
+    reader: &mut dyn BufRead,
+    buffer: &mut Zeroizing<Vec<u8>>,
+) -> io::Result<usize> {
+    let mut total = 0;
+
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+
+        let (used, done) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(index) => (index + 1, true),
+            None => (available.len(), available.is_empty()),
+        };
+
+        extend_zeroizing(buffer, &available[..used]);
+        reader.consume(used);
+        total += used;
+
+        if done {
+            return Ok(total);
+        }
+    }
+}
+
+/// Append to the buffer, moving it to a new zeroizing allocation when it is full
+fn extend_zeroizing(buffer: &mut Zeroizing<Vec<u8>>, bytes: &[u8]) {
+    if buffer.capacity() - buffer.len() < bytes.len() {
+        let capacity = (buffer.len() + bytes.len()).max(buffer.capacity() * 2);
+        let mut grown = Zeroizing::new(Vec::with_capacity(capacity));
+        grown.extend_from_slice(buffer);
+        *buffer = grown;
+    }
+
+    buffer.extend_from_slice(bytes);
+}
+
+/// Copy the reader into the writer through a buffer that is zeroized when dropped
+fn copy_zeroizing(mut reader: impl Read, mut writer: impl Write) -> io::Result<()> {
+    let mut chunk = Zeroizing::new([0; CHUNK_SIZE]);
+
+    loop {
+        match reader.read(chunk.as_mut_slice()) {
+            Ok(0) => return Ok(()),
+            Ok(read) => writer.write_all(&chunk[..read])?,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{encryption::EncryptedLabels, Labels};
@@ -100,4 +609,239 @@ mod tests {
 
         assert_eq!(labels, decrypted);
     }
+
+    #[test]
+    fn test_work_factor() {
+        use crate::{encryption::PassphraseOptions, error::EncryptionError};
+
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let options = PassphraseOptions::default().work_factor(10);
+
+        let encrypted =
+            EncryptedLabels::encrypt_with_options(&labels, "passphrase", options).unwrap();
+
+        let strict = PassphraseOptions::default().max_work_factor(9);
+        let error = encrypted
+            .decrypt_with_options("passphrase", strict)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            EncryptionError::ExcessiveWorkFactor { required: 10, .. }
+        ));
+
+        let relaxed = PassphraseOptions::default().max_work_factor(10);
+        let decrypted = encrypted
+            .decrypt_with_options("passphrase", relaxed)
+            .unwrap();
+        assert_eq!(labels, decrypted);
+
+        let invalid = PassphraseOptions::default().work_factor(64);
+        assert!(matches!(
+            EncryptedLabels::encrypt_with_options(&labels, "passphrase", invalid),
+            Err(EncryptionError::InvalidWorkFactor(64))
+        ));
+    }
+
+    #[test]
+    fn test_rekey() {
+        use crate::encryption::PassphraseOptions;
+
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let options = PassphraseOptions::default().work_factor(10);
+
+        let encrypted = EncryptedLabels::encrypt_with_options(&labels, "old", options).unwrap();
+        let rekeyed = encrypted.rekey_with_options("old", "new", options).unwrap();
+
+        assert_eq!(rekeyed.decrypt("new").unwrap(), labels);
+        assert!(rekeyed.decrypt("old").is_err());
+        assert!(encrypted.rekey("wrong", "new").is_err());
+    }
+
+    #[test]
+    fn test_error_classification() {
+        use std::iter;
+
+        use age::{x25519, Identity};
+
+        use crate::{encryption::PassphraseOptions, error::EncryptionError};
+
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let options = PassphraseOptions::default().work_factor(10);
+        let encrypted = EncryptedLabels::encrypt_with_options(&labels, "right", options).unwrap();
+
+        assert!(matches!(
+            encrypted.decrypt("wrong"),
+            Err(EncryptionError::WrongPassphrase)
+        ));
+
+        let mut truncated = encrypted.clone().into_bytes();
+        truncated.truncate(truncated.len() - 20);
+        assert!(matches!(
+            EncryptedLabels(truncated).decrypt("right"),
+            Err(EncryptionError::CorruptedCiphertext(_))
+        ));
+
+        assert!(matches!(
+            EncryptedLabels(b"not encrypted".to_vec()).decrypt("right"),
+            Err(EncryptionError::CorruptedCiphertext(_))
+        ));
+
+        let identity = x25519::Identity::generate();
+        let encrypt = |plaintext: &[u8]| {
+            let recipient = identity.to_public();
+            let encryptor = age::Encryptor::with_recipients(iter::once(&recipient as _)).unwrap();
+            EncryptedLabels::encrypt_with(
+                encryptor,
+                None,
+                |writer| Ok(writer.write_all(plaintext)?),
+            )
+            .unwrap()
+        };
+
+        let identities: &[Box<dyn Identity>] = &[Box::new(identity.clone())];
+        let other: &[Box<dyn Identity>] = &[Box::new(x25519::Identity::generate())];
+
+        assert!(matches!(
+            encrypt(b"").decrypt_with_identities(other),
+            Err(EncryptionError::NoMatchingKey)
+        ));
+        assert!(matches!(
+            encrypt(b"").decrypt("right"),
+            Err(EncryptionError::WrongPassphrase)
+        ));
+
+        // only a passphrase is ever wrong, identities fail with age's errors
+        assert!(matches!(
+            EncryptionError::from(age::DecryptError::DecryptionFailed),
+            EncryptionError::CorruptedCiphertext(_)
+        ));
+        assert!(matches!(
+            EncryptionError::from(age::DecryptError::KeyDecryptionFailed),
+            EncryptionError::DecryptError(age::DecryptError::KeyDecryptionFailed)
+        ));
+        assert!(matches!(
+            encrypt(&[0xff, 0xfe]).decrypt_with_identities(identities),
+            Err(EncryptionError::Utf8Error(_))
+        ));
+
+        let invalid = labels.export().unwrap() + "\n{\"type\": \"tx\"}";
+        let error = encrypt(invalid.as_bytes())
+            .decrypt_with_identities(identities)
+            .unwrap_err();
+
+        let EncryptionError::ParseError(error) = error else {
+            panic!("expected a parse error, got {error:?}");
+        };
+        assert_eq!(error.line(), Some(labels.len() + 1));
+    }
+
+    #[test]
+    fn test_armor_loop_back() {
+        let encrypted = EncryptedLabels::read_from_file("tests/data/encrypted_labels.age").unwrap();
+
+        let armor = encrypted.to_armor().unwrap();
+        assert!(armor.starts_with("-----BEGIN AGE ENCRYPTED FILE-----\n"));
+        assert!(armor.ends_with("-----END AGE ENCRYPTED FILE-----\n"));
+
+        assert_eq!(EncryptedLabels::from_armor(&armor).unwrap(), encrypted);
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        let encrypted = EncryptedLabels::read_from_file("tests/data/encrypted_labels.age").unwrap();
+
+        let armor = format!("\n{}\n", encrypted.to_armor().unwrap());
+        let hex = encrypted.to_hex().unwrap();
+        let binary = encrypted.clone().into_bytes();
+
+        for encoded in [armor.into_bytes(), hex.into_bytes(), binary] {
+            let decoded = EncryptedLabels::from_encoded(encoded).unwrap();
+            assert_eq!(decoded, encrypted);
+        }
+    }
+
+    #[test]
+    fn test_work_factor_with_header_and_compression() {
+        use crate::{
+            encryption::{LabelsHeader, PassphraseOptions},
+            error::EncryptionError,
+        };
+
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let options = PassphraseOptions::default().work_factor(10);
+        let strict = PassphraseOptions::default().max_work_factor(9);
+
+        let header = LabelsHeader::new(&labels);
+        let encrypted = EncryptedLabels::encrypt_with_header_and_options(
+            &labels,
+            "passphrase",
+            &header,
+            options,
+        )
+        .unwrap();
+
+        assert_eq!(encrypted.header().unwrap(), Some(header));
+        assert!(matches!(
+            encrypted.decrypt_with_options("passphrase", strict),
+            Err(EncryptionError::ExcessiveWorkFactor { required: 10, .. })
+        ));
+
+        #[cfg(feature = "gzip")]
+        {
+            use crate::compression::Compression;
+
+            let encrypted = EncryptedLabels::encrypt_with_compression_and_options(
+                &labels,
+                "passphrase",
+                Compression::Gzip,
+                options,
+            )
+            .unwrap();
+
+            assert!(matches!(
+                encrypted.decrypt_with_options("passphrase", strict),
+                Err(EncryptionError::ExcessiveWorkFactor { required: 10, .. })
+            ));
+            assert_eq!(encrypted.decrypt("passphrase").unwrap(), labels);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn test_encryption_with_compression() {
+        use crate::compression::Compression;
+
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let encrypted =
+            EncryptedLabels::encrypt_with_compression(&labels, "passphrase", Compression::Gzip)
+                .unwrap();
+        let uncompressed = EncryptedLabels::encrypt(&labels, "passphrase").unwrap();
+        assert!(encrypted.clone().into_bytes().len() < uncompressed.into_bytes().len());
+
+        let decrypted = encrypted.decrypt("passphrase").unwrap();
+        assert_eq!(labels, decrypted);
+    }
+
+    #[test]
+    fn read_line_zeroizing_spans_buffer_refills() {
+        use std::io::BufReader;
+
+        use zeroize::Zeroizing;
+
+        // a tiny buffer, so lines are split across several refills
+        let mut reader = BufReader::with_capacity(3, &b"first line\nsecond\n\nlast"[..]);
+        let mut lines = Vec::new();
+
+        loop {
+            let mut line = Zeroizing::new(Vec::new());
+            if super::read_line_zeroizing(&mut reader, &mut line).unwrap() == 0 {
+                break;
+            }
+
+            lines.push(String::from_utf8(line.to_vec()).unwrap());
+        }
+
+        assert_eq!(lines, ["first line\n", "second\n", "\n", "last"]);
+    }
 }
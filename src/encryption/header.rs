@@ -0,0 +1,261 @@
+use std::{
+    io::BufRead,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bitcoin::{bip32::Fingerprint, Network};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{EncryptionError, ExportError},
+    Labels,
+};
+
+/// The first line of encrypted labels that start with a plaintext header
+pub(crate) const MAGIC: &[u8] = b"bip329.encrypted-labels/v1\n";
+
+/// The header format version written by [`LabelsHeader::new`]
+pub const HEADER_VERSION: u8 = 1;
+
+/// Plaintext metadata stored in front of encrypted labels.
+///
+/// The header can be read with [`EncryptedLabels::header`](super::EncryptedLabels::header)
+/// without the passphrase or identity. A copy is encrypted along with the labels,
+/// and decryption fails with [`EncryptionError::HeaderMismatch`] when the two differ,
+/// so a modified header is detected once the labels are decrypted.
+///
+/// The encrypted container is the line `bip329.encrypted-labels/v1`, followed by the
+/// header as a single line JSON object, followed by the age ciphertext, whose
+/// plaintext starts with the same JSON line.
+#[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub struct LabelsHeader {
+    /// The header format version
+    pub version: u8,
+    /// The network of the wallet the labels belong to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<Network>,
+    /// The master fingerprint of the wallet the labels belong to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<Fingerprint>,
+    /// When the labels were encrypted, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// The number of label records
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_count: Option<u64>,
+}
+
+impl LabelsHeader {
+    /// Create a header for the labels, with the current time and record count.
+    pub fn new(labels: &Labels) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .ok();
+
+        Self {
+            version: HEADER_VERSION,
+            network: None,
+            fingerprint: None,
+            created_at,
+            record_count: Some(labels.len() as u64),
+        }
+    }
+
+    /// Set the network of the wallet the labels belong to
+    #[must_use]
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Set the master fingerprint of the wallet the labels belong to
+    #[must_use]
+    pub fn fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// The header encoded as the container prefix, the magic line and the JSON line
+    pub(crate) fn to_prefix(&self) -> Result<Vec<u8>, EncryptionError> {
+        let json = serde_json::to_vec(self).map_err(ExportError::from)?;
+        Ok([MAGIC, &json, b"\n"].concat())
+    }
+}
+
+/// Split the encrypted bytes into the header prefix, empty without a header, and the
+/// age ciphertext
+pub(crate) fn split(encrypted: &[u8]) -> Result<(&[u8], &[u8]), EncryptionError> {
+    let Some(rest) = encrypted.strip_prefix(MAGIC) else {
+        return Ok((&[], encrypted));
+    };
+
+    let line_end = rest
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .ok_or(EncryptionError::InvalidHeader)?;
+
+    Ok(encrypted.split_at(MAGIC.len() + line_end + 1))
+}
+
+/// Read the header prefix from the start of the reader, empty without a header
+pub(crate) fn read_prefix(reader: &mut impl BufRead) -> Result<Vec<u8>, EncryptionError> {
+    let mut prefix = Vec::new();
+
+    // reading fails when the armor or the underlying file is invalid
+    let start = reader
+        .fill_buf()
+        .map_err(EncryptionError::CorruptedCiphertext)?;
+
+    if start.starts_with(MAGIC) {
+        for _ in 0..2 {
+            reader
+                .read_until(b'\n', &mut prefix)
+                .map_err(EncryptionError::CorruptedCiphertext)?;
+        }
+        parse(&prefix)?;
+    }
+
+    Ok(prefix)
+}
+
+/// Parse the header prefix returned by [`split`] or [`read_prefix`]
+pub(crate) fn parse(prefix: &[u8]) -> Result<Option<LabelsHeader>, EncryptionError> {
+    if prefix.is_empty() {
+        return Ok(None);
+    }
+
+    let line = json_line(prefix).ok_or(EncryptionError::InvalidHeader)?;
+
+    let header: LabelsHeader =
+        serde_json::from_slice(line).map_err(|_| EncryptionError::InvalidHeader)?;
+
+    if header.version != HEADER_VERSION {
+        return Err(EncryptionError::UnsupportedHeaderVersion(header.version));
+    }
+
+    Ok(Some(header))
+}
+
+/// Check the decrypted copy of the header matches the plaintext header, returning the
+/// plaintext after the copy
+pub(crate) fn strip<'a>(prefix: &[u8], plaintext: &'a [u8]) -> Result<&'a [u8], EncryptionError> {
+    let Some(line) = json_line(prefix) else {
+        return Ok(plaintext);
+    };
+
+    plaintext
+        .strip_prefix(line)
+        .and_then(|plaintext| plaintext.strip_prefix(b"\n"))
+        .ok_or(EncryptionError::HeaderMismatch)
+}
+
+/// The JSON line of the header prefix, without the trailing newline
+fn json_line(prefix: &[u8]) -> Option<&[u8]> {
+    prefix
+        .strip_prefix(MAGIC)
+        .and_then(|line| line.strip_suffix(b"\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use age::x25519;
+    use bitcoin::bip32::Fingerprint;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::encryption::EncryptedLabels;
+
+    fn header(labels: &Labels) -> LabelsHeader {
+        LabelsHeader::new(labels)
+            .network(Network::Signet)
+            .fingerprint(Fingerprint::from([0xd3, 0x4d, 0xb3, 0x3f]))
+    }
+
+    #[test]
+    fn header_is_readable_without_decrypting() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let identity = x25519::Identity::generate();
+        let header = header(&labels);
+
+        let encrypted = EncryptedLabels::encrypt_to_recipients_with_header(
+            &labels,
+            &[Box::new(identity.to_public())],
+            &header,
+        )
+        .unwrap();
+
+        let armored = EncryptedLabels::from_encoded(encrypted.to_armor().unwrap().into()).unwrap();
+        assert_eq!(armored, encrypted);
+
+        let read = encrypted.header().unwrap().unwrap();
+        assert_eq!(read, header);
+        assert_eq!(read.record_count, Some(labels.len() as u64));
+
+        let decrypted = encrypted
+            .decrypt_with_identities(&[Box::new(identity)])
+            .unwrap();
+        assert_eq!(decrypted, labels);
+    }
+
+    #[test]
+    fn modified_header_fails_decryption() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let identity = x25519::Identity::generate();
+        let recipients: &[Box<dyn age::Recipient + Send>] = &[Box::new(identity.to_public())];
+
+        let encrypted = EncryptedLabels::encrypt_to_recipients_with_header(
+            &labels,
+            recipients,
+            &header(&labels),
+        )
+        .unwrap();
+
+        let bytes = encrypted.into_bytes();
+        let (prefix, ciphertext) = split(&bytes).unwrap();
+
+        let modified = header(&labels)
+            .network(Network::Bitcoin)
+            .to_prefix()
+            .unwrap();
+        assert_eq!(modified.len(), prefix.len() + 1);
+
+        let modified = EncryptedLabels::from_encoded([&modified, ciphertext].concat()).unwrap();
+        assert!(matches!(
+            modified.decrypt_with_identities(&[Box::new(identity)]),
+            Err(EncryptionError::HeaderMismatch)
+        ));
+
+        let plain = EncryptedLabels::encrypt_to_recipients(&labels, recipients).unwrap();
+        assert_eq!(plain.header().unwrap(), None);
+    }
+
+    #[test]
+    fn header_survives_rekey_and_streaming() {
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let header = header(&labels);
+
+        let encrypted = EncryptedLabels::encrypt_with_header(&labels, "old", &header).unwrap();
+        let rekeyed = encrypted.rekey("old", "new").unwrap();
+        assert_eq!(rekeyed.header().unwrap(), Some(header));
+
+        let bytes = rekeyed.into_bytes();
+        let decrypted = EncryptedLabels::decrypt_from_reader(bytes.as_slice(), "new")
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(Labels::new(decrypted), labels);
+    }
+
+    #[test]
+    fn unreadable_prefix_is_corrupted() {
+        let armor = "-----BEGIN AGE ENCRYPTED FILE-----\n!!!!\n-----END AGE ENCRYPTED FILE-----\n";
+        let mut reader = std::io::BufReader::new(age::armor::ArmoredReader::new(armor.as_bytes()));
+
+        assert!(matches!(
+            read_prefix(&mut reader),
+            Err(EncryptionError::CorruptedCiphertext(_))
+        ));
+    }
+}
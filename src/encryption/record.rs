@@ -0,0 +1,382 @@
+//! Per-record label encryption, for partial disclosure.
+//!
+//! Each record keeps its `type` and `ref` in the clear, so a server can index and
+//! merge records by reference, while the `label` text is encrypted with
+//! XChaCha20-Poly1305. Transaction `origin` and output `spendable` fields are also
+//! encrypted when enabled in [`RecordEncryptionOptions`], otherwise they stay
+//! visible.
+//!
+//! The visible fields of a record are authenticated as associated data, so the
+//! ciphertext can't be moved to another record, and the visible fields can't be
+//! changed, without decryption failing.
+//!
+//! Encrypted records are exported as JSONL, one object per record:
+//!
+//! ```json
+//! {"type":"tx","ref":"f91d...","origin":"wpkh([d34db33f/84'/0'/0'])","ciphertext":"9c1b..."}
+//! ```
+//!
+//! `ciphertext` is the hex encoded 24 byte nonce followed by the encrypted JSON
+//! object of the encrypted fields.
+
+use std::{fmt, ops::Deref};
+
+use chacha20poly1305::{
+    aead::{Aead as _, AeadCore as _, KeyInit as _, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use zeroize::Zeroizing;
+
+use crate::{
+    error::{EncryptionError, ExportError, ParseError},
+    Label, Labels,
+};
+
+const NONCE_LENGTH: usize = 24;
+
+/// Prefix of the associated data, versioning the record encryption scheme
+const AAD_PREFIX: &[u8] = b"bip329/record/v1\0";
+
+/// A 256 bit key for encrypting individual label records.
+///
+/// The key is zeroized when dropped.
+#[derive(Clone)]
+pub struct RecordKey(Zeroizing<[u8; 32]>);
+
+impl RecordKey {
+    /// Generate a new random key.
+    pub fn generate() -> Self {
+        Self::from_bytes(XChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    /// Create a key from raw bytes.
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(Zeroizing::new(bytes))
+    }
+
+    /// The raw key bytes, for storing the key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&(*self.0).into())
+    }
+}
+
+impl fmt::Debug for RecordKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RecordKey([REDACTED])")
+    }
+}
+
+/// Options for choosing which record fields are encrypted
+///
+/// The `label` field is always encrypted.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct RecordEncryptionOptions {
+    pub(crate) encrypt_origin: bool,
+    pub(crate) encrypt_spendable: bool,
+}
+
+impl RecordEncryptionOptions {
+    /// Encrypt the `origin` field of transaction records
+    #[must_use]
+    pub fn encrypt_origin(mut self, encrypt: bool) -> Self {
+        self.encrypt_origin = encrypt;
+        self
+    }
+
+    /// Encrypt the `spendable` field of output records
+    #[must_use]
+    pub fn encrypt_spendable(mut self, encrypt: bool) -> Self {
+        self.encrypt_spendable = encrypt;
+        self
+    }
+}
+
+/// A label record whose sensitive fields are encrypted.
+#[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EncryptedRecord {
+    #[serde(rename = "type")]
+    type_: String,
+    #[serde(rename = "ref")]
+    ref_: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    origin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spendable: Option<bool>,
+    #[serde(with = "hex")]
+    ciphertext: Vec<u8>,
+}
+
+/// The encrypted fields of a record
+#[derive(Default, Serialize, Deserialize)]
+struct SealedFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    origin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spendable: Option<bool>,
+}
+
+impl EncryptedRecord {
+    /// Encrypt a single label record with the given key.
+    pub fn encrypt(
+        label: &Label,
+        key: &RecordKey,
+        options: RecordEncryptionOptions,
+    ) -> Result<Self, EncryptionError> {
+        let ref_ = label.ref_();
+
+        let mut sealed = SealedFields {
+            label: label.label().map(ToString::to_string),
+            ..Default::default()
+        };
+
+        let (mut origin, mut spendable) = (None, None);
+        match label {
+            Label::Transaction(record) if options.encrypt_origin => {
+                sealed.origin.clone_from(&record.origin)
+            }
+            Label::Transaction(record) => origin.clone_from(&record.origin),
+            Label::Output(record) if options.encrypt_spendable => {
+                sealed.spendable = record.spendable
+            }
+            Label::Output(record) => spendable = record.spendable,
+            _ => {}
+        }
+
+        let mut record = Self {
+            type_: ref_.type_tag().to_string(),
+            ref_: ref_.to_string(),
+            origin,
+            spendable,
+            ciphertext: Vec::new(),
+        };
+
+        let plaintext = Zeroizing::new(serde_json::to_vec(&sealed).map_err(ExportError::from)?);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: &plaintext,
+            aad: &record.associated_data()?,
+        };
+
+        let ciphertext = key
+            .cipher()
+            .encrypt(&nonce, payload)
+            .expect("encryption into a Vec can't fail");
+
+        record.ciphertext = [nonce.as_slice(), &ciphertext].concat();
+        Ok(record)
+    }
+
+    /// Decrypt the record with the given key.
+    ///
+    /// Fails with [`EncryptionError::RecordDecryptError`] when the key is wrong, or the
+    /// ciphertext or any visible field was modified.
+    pub fn decrypt(&self, key: &RecordKey) -> Result<Label, EncryptionError> {
+        if self.ciphertext.len() < NONCE_LENGTH {
+            return Err(EncryptionError::RecordDecryptError);
+        }
+
+        let (nonce, ciphertext) = self.ciphertext.split_at(NONCE_LENGTH);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: &self.associated_data()?,
+        };
+
+        let plaintext = key
+            .cipher()
+            .decrypt(XNonce::from_slice(nonce), payload)
+            .map(Zeroizing::new)
+            .map_err(|_| EncryptionError::RecordDecryptError)?;
+
+        let sealed: SealedFields = serde_json::from_slice(&plaintext).map_err(ParseError::from)?;
+
+        let mut fields = Map::new();
+        fields.insert("type".to_string(), self.type_.clone().into());
+        fields.insert("ref".to_string(), self.ref_.clone().into());
+
+        if let Some(label) = sealed.label {
+            fields.insert("label".to_string(), label.into());
+        }
+        if let Some(origin) = sealed.origin.or_else(|| self.origin.clone()) {
+            fields.insert("origin".to_string(), origin.into());
+        }
+        if let Some(spendable) = sealed.spendable.or(self.spendable) {
+            fields.insert("spendable".to_string(), spendable.into());
+        }
+
+        let label = serde_json::from_value(Value::Object(fields)).map_err(ParseError::from)?;
+        Ok(label)
+    }
+
+    /// The BIP329 `type` of the record.
+    pub fn type_tag(&self) -> &str {
+        &self.type_
+    }
+
+    /// The `ref` of the record, as it appears in BIP329 JSONL.
+    pub fn ref_(&self) -> &str {
+        &self.ref_
+    }
+
+    /// A key identifying the record, `type:ref`, for merging records by reference.
+    ///
+    /// Matches the key used by the built-in label repositories.
+    pub fn storage_key(&self) -> String {
+        format!("{}:{}", self.type_, self.ref_)
+    }
+
+    fn associated_data(&self) -> Result<Vec<u8>, EncryptionError> {
+        let visible = (&self.type_, &self.ref_, &self.origin, &self.spendable);
+        let visible = serde_json::to_vec(&visible).map_err(ExportError::from)?;
+
+        Ok([AAD_PREFIX, &visible].concat())
+    }
+}
+
+/// A list of label records whose sensitive fields are encrypted.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EncryptedRecords(Vec<EncryptedRecord>);
+
+impl EncryptedRecords {
+    /// Encrypt each record of the Labels struct with the given key.
+    pub fn encrypt(
+        labels: &Labels,
+        key: &RecordKey,
+        options: RecordEncryptionOptions,
+    ) -> Result<Self, EncryptionError> {
+        let records = labels
+            .iter()
+            .map(|label| EncryptedRecord::encrypt(label, key, options))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self(records))
+    }
+
+    /// Decrypt all records with the given key.
+    pub fn decrypt(&self, key: &RecordKey) -> Result<Labels, EncryptionError> {
+        let labels = self
+            .0
+            .iter()
+            .map(|record| record.decrypt(key))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Labels::new(labels))
+    }
+
+    /// Create a new EncryptedRecords struct from a JSONL string.
+    pub fn try_from_str(records: &str) -> Result<Self, ParseError> {
+        let records = records
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self(records))
+    }
+
+    /// Export the encrypted records to a JSONL string.
+    pub fn export(&self) -> Result<String, ExportError> {
+        let contents = self
+            .0
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(contents.join("\n"))
+    }
+
+    /// Get the inner Vec of the EncryptedRecords struct.
+    #[must_use]
+    pub fn into_vec(self) -> Vec<EncryptedRecord> {
+        self.0
+    }
+}
+
+impl Deref for EncryptedRecords {
+    type Target = Vec<EncryptedRecord>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<EncryptedRecord>> for EncryptedRecords {
+    fn from(records: Vec<EncryptedRecord>) -> Self {
+        Self(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn record_loop_back() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let key = RecordKey::generate();
+
+        let encrypted =
+            EncryptedRecords::encrypt(&labels, &key, RecordEncryptionOptions::default()).unwrap();
+        let exported = encrypted.export().unwrap();
+
+        for label in labels.iter().filter_map(Label::label) {
+            assert!(!exported.contains(label));
+        }
+
+        let imported = EncryptedRecords::try_from_str(&exported).unwrap();
+        assert_eq!(imported.decrypt(&key).unwrap(), labels);
+        assert!(imported.decrypt(&RecordKey::generate()).is_err());
+    }
+
+    #[test]
+    fn optional_fields_are_visible_unless_encrypted() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let key = RecordKey::generate();
+        let tx = labels
+            .iter()
+            .find(|label| matches!(label, Label::Transaction(record) if record.origin.is_some()))
+            .unwrap();
+
+        let visible = EncryptedRecord::encrypt(tx, &key, Default::default()).unwrap();
+        assert!(visible.origin.is_some());
+
+        let options = RecordEncryptionOptions::default().encrypt_origin(true);
+        let sealed = EncryptedRecord::encrypt(tx, &key, options).unwrap();
+        assert_eq!(sealed.origin, None);
+
+        assert_eq!(&visible.decrypt(&key).unwrap(), tx);
+        assert_eq!(&sealed.decrypt(&key).unwrap(), tx);
+    }
+
+    #[test]
+    fn ciphertext_is_bound_to_the_record_ref() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let key = RecordKey::generate();
+        let options = RecordEncryptionOptions::default();
+
+        let mut first = EncryptedRecord::encrypt(&labels[0], &key, options).unwrap();
+        let second = EncryptedRecord::encrypt(&labels[1], &key, options).unwrap();
+        first.ciphertext = second.ciphertext.clone();
+
+        assert!(matches!(
+            first.decrypt(&key),
+            Err(EncryptionError::RecordDecryptError)
+        ));
+
+        let mut moved = second.clone();
+        moved.type_ = "output".to_string();
+        assert!(matches!(
+            moved.decrypt(&key),
+            Err(EncryptionError::RecordDecryptError)
+        ));
+    }
+}
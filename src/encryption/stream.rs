@@ -0,0 +1,219 @@
+use std::io::{BufRead, BufReader, Read, Write};
+
+use age::armor::ArmoredReader;
+use zeroize::Zeroizing;
+
+use super::{header, passphrase_error, read_line_zeroizing, EncryptedLabels, PassphraseOptions};
+use crate::{error::EncryptionError, Label, Labels};
+
+/// An iterator over labels decrypted from a reader, one JSONL line at a time.
+///
+/// Created with [`EncryptedLabels::decrypt_from_reader`].
+pub struct LabelReader<'a> {
+    reader: Box<dyn BufRead + 'a>,
+    line: Zeroizing<Vec<u8>>,
+    line_number: usize,
+}
+
+impl EncryptedLabels {
+    /// Encrypt the Labels struct using the given passphrase, writing the ciphertext to the writer.
+    ///
+    /// Labels are encrypted as they are exported, without buffering the exported
+    /// labels or the ciphertext in memory. Returns the writer once encryption is finished.
+    pub fn encrypt_to_writer<W: Write>(
+        labels: &Labels,
+        passphrase: &str,
+        writer: W,
+    ) -> Result<W, EncryptionError> {
+        Self::encrypt_to_writer_with_options(
+            labels,
+            passphrase,
+            PassphraseOptions::default(),
+            writer,
+        )
+    }
+
+    /// Encrypt the Labels struct using the given passphrase and scrypt options, writing
+    /// the ciphertext to the writer.
+    pub fn encrypt_to_writer_with_options<W: Write>(
+        labels: &Labels,
+        passphrase: &str,
+        options: PassphraseOptions,
+        writer: W,
+    ) -> Result<W, EncryptionError> {
+        let encryptor = options.encryptor(passphrase)?;
+
+        let mut writer = encryptor.wrap_output(writer)?;
+        labels.export_to_writer(&mut writer)?;
+
+        Ok(writer.finish()?)
+    }
+
+    /// Decrypt labels from a reader using the given passphrase.
+    ///
+    /// Accepts binary and ASCII armored input, with or without a
+    /// [`LabelsHeader`](super::LabelsHeader). Labels are decrypted and parsed one line at
+    /// a time as the returned iterator is advanced, so memory use does not grow with the
+    /// size of the label set.
+    pub fn decrypt_from_reader<'a, R: Read + 'a>(
+        reader: R,
+        passphrase: &str,
+    ) -> Result<LabelReader<'a>, EncryptionError> {
+        Self::decrypt_from_reader_with_options(reader, passphrase, PassphraseOptions::default())
+    }
+
+    /// Decrypt labels from a reader using the given passphrase and scrypt options.
+    ///
+    /// See [`EncryptedLabels::decrypt_from_reader`].
+    pub fn decrypt_from_reader_with_options<'a, R: Read + 'a>(
+        reader: R,
+        passphrase: &str,
+        options: PassphraseOptions,
+    ) -> Result<LabelReader<'a>, EncryptionError> {
+        let identity = options.identity(passphrase)?;
+
+        let mut reader = BufReader::new(ArmoredReader::new(reader));
+        let prefix = header::read_prefix(&mut reader)?;
+
+        let decryptor = age::Decryptor::new_buffered(reader)?;
+        let reader = decryptor
+            .decrypt(std::iter::once(&identity as _))
+            .map_err(passphrase_error)?;
+        let mut reader = BufReader::new(reader);
+
+        if !prefix.is_empty() {
+            let mut copy = Zeroizing::new(Vec::new());
+            read_line_zeroizing(&mut reader, &mut copy)
+                .map_err(EncryptionError::CorruptedCiphertext)?;
+            header::strip(&prefix, &copy)?;
+        }
+
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        let reader = crate::compression::decompressing_reader(reader)
+            .map_err(EncryptionError::CompressionError)?;
+
+        Ok(LabelReader {
+            reader: Box::new(reader),
+            line: Zeroizing::new(Vec::new()),
+            line_number: 0,
+        })
+    }
+}
+
+impl Iterator for LabelReader<'_> {
+    type Item = Result<Label, EncryptionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            self.line_number += 1;
+
+            match read_line_zeroizing(&mut self.reader, &mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => match parse_line(&self.line, self.line_number) {
+                    Ok(Some(label)) => return Some(Ok(label)),
+                    Ok(None) => continue,
+                    Err(error) => return Some(Err(error)),
+                },
+                Err(error) => return Some(Err(EncryptionError::CorruptedCiphertext(error))),
+            }
+        }
+    }
+}
+
+/// Parse a decrypted JSONL line, `None` for blank lines
+fn parse_line(line: &[u8], line_number: usize) -> Result<Option<Label>, EncryptionError> {
+    let line = std::str::from_utf8(line)?;
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let label = Label::try_from_str(line).map_err(|error| error.at_line(line_number))?;
+    Ok(Some(label))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn encrypt_to_writer_loop_back() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let encrypted = EncryptedLabels::encrypt_to_writer(&labels, "passphrase", vec![]).unwrap();
+
+        let decrypted = EncryptedLabels::decrypt_from_reader(encrypted.as_slice(), "passphrase")
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(Labels::new(decrypted), labels);
+
+        let decrypted = EncryptedLabels::from_encoded(encrypted)
+            .unwrap()
+            .decrypt("passphrase")
+            .unwrap();
+        assert_eq!(decrypted, labels);
+    }
+
+    #[test]
+    fn decrypt_from_file_reader() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        for path in [
+            "tests/data/encrypted_labels.age",
+            "tests/data/encrypted_labels.age.asc",
+        ] {
+            let file = File::open(path).unwrap();
+            let decrypted = EncryptedLabels::decrypt_from_reader(file, "passphrase")
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            assert_eq!(Labels::new(decrypted), labels);
+        }
+    }
+
+    #[test]
+    fn decrypt_from_reader_wrong_passphrase() {
+        let file = File::open("tests/data/encrypted_labels.age").unwrap();
+        assert!(matches!(
+            EncryptedLabels::decrypt_from_reader(file, "wrong"),
+            Err(EncryptionError::WrongPassphrase)
+        ));
+    }
+
+    #[test]
+    fn stream_with_options() {
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let options = PassphraseOptions::default().work_factor(10);
+
+        let encrypted =
+            EncryptedLabels::encrypt_to_writer_with_options(&labels, "passphrase", options, vec![])
+                .unwrap();
+
+        let strict = PassphraseOptions::default().max_work_factor(9);
+        assert!(matches!(
+            EncryptedLabels::decrypt_from_reader_with_options(
+                encrypted.as_slice(),
+                "passphrase",
+                strict
+            ),
+            Err(EncryptionError::ExcessiveWorkFactor { required: 10, .. })
+        ));
+
+        let relaxed = PassphraseOptions::default().max_work_factor(10);
+        let decrypted = EncryptedLabels::decrypt_from_reader_with_options(
+            encrypted.as_slice(),
+            "passphrase",
+            relaxed,
+        )
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+        assert_eq!(Labels::new(decrypted), labels);
+    }
+}
@@ -0,0 +1,161 @@
+//! Deterministic label encryption keys derived from a wallet.
+//!
+//! Any device holding the wallet's extended private key derives the same age
+//! X25519 identity, so label backups can be decrypted without a separate passphrase.
+//!
+//! The identity is derived from an extended private key, usually the account xprv:
+//!
+//! 1. Derive the hardened child `329'` of the extended private key (BIP32).
+//! 2. Compute the BIP340 style tagged hash `SHA256(SHA256(tag) || SHA256(tag) || key)`
+//!    where `tag` is the ASCII string `bip329/age-x25519` and `key` is the 32 byte
+//!    private key of the child.
+//! 3. Use the 32 byte hash as the age X25519 secret key, which is encoded as the
+//!    uppercase bech32 string with the `AGE-SECRET-KEY-` human readable part.
+//!
+//! The [`RecordKey`] for per-record encryption is derived the same way, with the
+//! tag `bip329/record-key`, and the 32 byte hash used as the key directly.
+//!
+//! Only holders of the private key can derive the identity, watch-only wallets
+//! can't decrypt backups made with it.
+
+use age::x25519;
+use bitcoin::{
+    bech32::{self, Bech32, Hrp},
+    bip32::{ChildNumber, Xpriv},
+    hashes::{sha256, Hash as _, HashEngine as _},
+    secp256k1::Secp256k1,
+};
+
+use zeroize::Zeroizing;
+
+use super::{record::RecordKey, EncryptedLabels};
+use crate::{error::EncryptionError, Labels};
+
+/// The hardened child index the encryption key is derived from
+pub const WALLET_KEY_CHILD_INDEX: u32 = 329;
+
+const WALLET_KEY_TAG: &[u8] = b"bip329/age-x25519";
+
+const RECORD_KEY_TAG: &[u8] = b"bip329/record-key";
+
+/// Derive the age identity used to encrypt labels for the given wallet.
+///
+/// See the [module documentation](self) for the derivation scheme.
+pub fn wallet_identity(xprv: &Xpriv) -> Result<x25519::Identity, EncryptionError> {
+    let secret = derive_secret(xprv, WALLET_KEY_TAG)?;
+
+    let hrp = Hrp::parse("age-secret-key-").expect("valid hrp");
+    let encoded = bech32::encode::<Bech32>(hrp, secret.as_ref())
+        .expect("32 bytes fit in a bech32 string")
+        .to_uppercase();
+
+    let identity = encoded
+        .parse::<x25519::Identity>()
+        .expect("valid age identity encoding");
+
+    Ok(identity)
+}
+
+/// Derive the key used to encrypt individual label records for the given wallet.
+///
+/// See the [module documentation](self) for the derivation scheme.
+pub fn wallet_record_key(xprv: &Xpriv) -> Result<RecordKey, EncryptionError> {
+    let secret = derive_secret(xprv, RECORD_KEY_TAG)?;
+    Ok(RecordKey::from_bytes(*secret))
+}
+
+/// Tagged hash of the private key of the `329'` child
+fn derive_secret(xprv: &Xpriv, tag: &[u8]) -> Result<Zeroizing<[u8; 32]>, EncryptionError> {
+    let secp = Secp256k1::signing_only();
+    let child = ChildNumber::from_hardened_idx(WALLET_KEY_CHILD_INDEX)
+        .and_then(|child| xprv.derive_priv(&secp, &[child]))
+        .map_err(EncryptionError::WalletKeyError)?;
+
+    let tag = sha256::Hash::hash(tag);
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(&Zeroizing::new(child.private_key.secret_bytes())[..]);
+
+    Ok(Zeroizing::new(
+        sha256::Hash::from_engine(engine).to_byte_array(),
+    ))
+}
+
+impl EncryptedLabels {
+    /// Encrypt the Labels struct with a key derived from the wallet.
+    ///
+    /// See [`wallet_identity`] for how the key is derived.
+    pub fn encrypt_with_wallet_key(labels: &Labels, xprv: &Xpriv) -> Result<Self, EncryptionError> {
+        let recipient = wallet_identity(xprv)?.to_public();
+        Self::encrypt_to_recipients(labels, &[Box::new(recipient)])
+    }
+
+    /// Decrypt labels encrypted with [`EncryptedLabels::encrypt_with_wallet_key`].
+    pub fn decrypt_with_wallet_key(&self, xprv: &Xpriv) -> Result<Labels, EncryptionError> {
+        let identity = wallet_identity(xprv)?;
+        self.decrypt_with_identities(&[Box::new(identity)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use age::secrecy::ExposeSecret as _;
+
+    use super::*;
+
+    // BIP32 test vector 1 master key
+    const XPRV: &str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+
+    #[test]
+    fn wallet_identity_test_vector() {
+        let xprv = Xpriv::from_str(XPRV).unwrap();
+        let identity = wallet_identity(&xprv).unwrap();
+
+        assert_eq!(
+            identity.to_string().expose_secret(),
+            "AGE-SECRET-KEY-1GD8KPL0J99YTNSCAN4XCZWWYVCSHRQYRQ2TVGEV5F4E4JWX64RMQZR0UPG"
+        );
+        assert_eq!(
+            identity.to_public().to_string(),
+            "age1a6lll5z6387efats2xv25f4jy9he4wf49k7qcevhm9mg0udg9qms9rvzr3"
+        );
+    }
+
+    #[test]
+    fn wallet_record_key_test_vector() {
+        let xprv = Xpriv::from_str(XPRV).unwrap();
+        let key = wallet_record_key(&xprv).unwrap();
+
+        assert_eq!(
+            hex::encode(key.as_bytes()),
+            "e3c826f988b0673da5585d7b96594de86ffab953590f7d2b1b873d23cebb650c"
+        );
+    }
+
+    #[test]
+    fn decrypt_wallet_encrypted_test_vector() {
+        let xprv = Xpriv::from_str(XPRV).unwrap();
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let encrypted =
+            EncryptedLabels::read_from_file("tests/data/wallet_encrypted_labels.age").unwrap();
+        let decrypted = encrypted.decrypt_with_wallet_key(&xprv).unwrap();
+
+        assert_eq!(labels, decrypted);
+    }
+
+    #[test]
+    fn wallet_key_loop_back() {
+        let xprv = Xpriv::from_str(XPRV).unwrap();
+        let other = Xpriv::new_master(bitcoin::NetworkKind::Main, &[7; 32]).unwrap();
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+
+        let encrypted = EncryptedLabels::encrypt_with_wallet_key(&labels, &xprv).unwrap();
+
+        assert_eq!(encrypted.decrypt_with_wallet_key(&xprv).unwrap(), labels);
+        assert!(encrypted.decrypt_with_wallet_key(&other).is_err());
+    }
+}
@@ -2,16 +2,43 @@
 
 /// Errors that can occur when parsing a label.
 #[derive(Debug, thiserror::Error)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error), uniffi(flat_error))]
 pub enum ParseError {
     #[error("Unable to read file: {0}")]
     FileReadError(#[from] std::io::Error),
 
     #[error("Unable to parse file: {0}")]
     ParseError(#[from] serde_json::Error),
+
+    #[error("Invalid label on line {line}: {source}")]
+    InvalidLine {
+        /// The 1-based line number of the invalid record
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+impl ParseError {
+    /// The 1-based line number of the invalid record, when parsing JSONL
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Self::InvalidLine { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+
+    /// Attach the line number to a JSON error
+    pub(crate) fn at_line(self, line: usize) -> Self {
+        match self {
+            Self::ParseError(source) => Self::InvalidLine { line, source },
+            error => error,
+        }
+    }
 }
 
 /// Errors that can occur when exporting a label.
 #[derive(Debug, thiserror::Error)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error), uniffi(flat_error))]
 pub enum ExportError {
     #[error("Unable to write file: {0}")]
     FileWriteError(#[from] std::io::Error),
@@ -20,28 +47,242 @@ pub enum ExportError {
     SerializeError(#[from] serde_json::Error),
 }
 
+/// Errors that can occur when encoding or decoding labels in the binary format.
+#[cfg(feature = "binary")]
+#[derive(Debug, thiserror::Error)]
+pub enum BinaryError {
+    #[error("Missing or invalid binary label header")]
+    InvalidHeader,
+
+    #[error("Unsupported binary label format version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Unable to encode or decode labels: {0}")]
+    PostcardError(#[from] postcard::Error),
+}
+
 /// Errors that can occur when encrypting or decrypting a label.
 #[cfg(feature = "encryption")]
 #[derive(Debug, thiserror::Error)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error), uniffi(flat_error))]
 pub enum EncryptionError {
     #[error("Unable to encrypt labels: {0}")]
     EncryptError(#[from] age::EncryptError),
 
-    #[error("Unable to encrypt labels: {0}")]
-    DecryptError(#[from] age::DecryptError),
+    #[error("Unable to decrypt labels: {0}")]
+    DecryptError(age::DecryptError),
+
+    #[error("Wrong passphrase, or the encrypted labels header is corrupted")]
+    WrongPassphrase,
+
+    #[error("The labels were not encrypted to the passphrase or any of the identities")]
+    NoMatchingKey,
+
+    #[error("The scrypt work factor {required} is above the maximum of {max}")]
+    ExcessiveWorkFactor { required: u8, max: u8 },
+
+    #[error("The encrypted labels are truncated or corrupted: {0}")]
+    CorruptedCiphertext(std::io::Error),
 
-    #[error("Unable to parse labels: {0}")]
+    #[error("Decrypted labels are not valid BIP329: {0}")]
     ParseError(#[from] ParseError),
 
     #[error("Unable to export labels: {0}")]
     ExportError(#[from] ExportError),
 
-    #[error("Unable to write labels: {0}")]
+    #[error("Unable to read or write labels: {0}")]
     WriteError(#[from] std::io::Error),
 
-    #[error("Decrypted to invalid UTF-8 string: {0}")]
-    Utf8Error(#[from] std::string::FromUtf8Error),
+    #[error("Decrypted labels are not valid UTF-8: {0}")]
+    Utf8Error(#[from] std::str::Utf8Error),
 
     #[error("Invalid hex encoded string: {0}")]
     HexError(#[from] hex::FromHexError),
+
+    #[error("Invalid scrypt work factor {0}, must be between 1 and 63")]
+    InvalidWorkFactor(u8),
+
+    #[error("Invalid ASCII armor: {0}")]
+    ArmorError(std::io::Error),
+
+    #[error("Invalid age or SSH recipient")]
+    InvalidRecipient,
+
+    #[error("Invalid identity file: {0}")]
+    InvalidIdentity(std::io::Error),
+
+    #[error("Invalid encrypted labels header")]
+    InvalidHeader,
+
+    #[error("Unsupported encrypted labels header version: {0}")]
+    UnsupportedHeaderVersion(u8),
+
+    #[error(
+        "The encrypted labels header does not match its encrypted copy, the header was modified"
+    )]
+    HeaderMismatch,
+
+    #[error("Unable to decrypt label record, the key is wrong or the record was modified")]
+    RecordDecryptError,
+
+    #[error("Unable to derive wallet encryption key: {0}")]
+    WalletKeyError(bitcoin::bip32::Error),
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[error("Unable to compress or decompress labels: {0}")]
+    CompressionError(std::io::Error),
+}
+
+#[cfg(feature = "encryption")]
+impl From<age::DecryptError> for EncryptionError {
+    fn from(error: age::DecryptError) -> Self {
+        use age::DecryptError;
+
+        // a wrong passphrase is mapped where the passphrase's file key is decrypted, an
+        // identity failing to decrypt is not one
+        match error {
+            DecryptError::NoMatchingKeys => Self::NoMatchingKey,
+            DecryptError::ExcessiveWork { required, target } => Self::ExcessiveWorkFactor {
+                required,
+                max: target,
+            },
+            DecryptError::DecryptionFailed
+            | DecryptError::InvalidHeader
+            | DecryptError::InvalidMac
+            | DecryptError::UnknownFormat => {
+                let error = std::io::Error::new(std::io::ErrorKind::InvalidData, error);
+                Self::CorruptedCiphertext(error)
+            }
+            DecryptError::Io(error) => Self::CorruptedCiphertext(error),
+            // an identity file's key that can't be decrypted, and plugin errors
+            error => Self::DecryptError(error),
+        }
+    }
+}
+
+/// Errors that can occur when signing labels or verifying a label signature.
+#[cfg(feature = "signing")]
+#[derive(Debug, thiserror::Error)]
+pub enum SignatureError {
+    #[error("Invalid signature, the labels were modified or signed by another key")]
+    InvalidSignature,
+
+    #[error("Invalid base64 encoded BIP322 signature")]
+    InvalidEncoding,
+
+    #[error("The signature fingerprint does not match the signing key")]
+    FingerprintMismatch,
+
+    #[error("The signing key does not match the expected signer")]
+    SignerMismatch,
+
+    #[error("Only P2WPKH and P2TR addresses are supported")]
+    UnsupportedAddress,
+
+    #[error("Unable to export labels: {0}")]
+    ExportError(#[from] ExportError),
+
+    #[error("Unable to read or write signature file: {0}")]
+    FileError(#[from] std::io::Error),
+
+    #[error("Unable to parse or serialize signature: {0}")]
+    SerdeError(#[from] serde_json::Error),
+}
+
+/// Errors that can occur when reading or writing a label repository.
+#[cfg(feature = "redb")]
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoryError {
+    #[error("Unable to open database: {0}")]
+    DatabaseError(#[from] redb::DatabaseError),
+
+    #[error("Unable to start transaction: {0}")]
+    TransactionError(Box<redb::TransactionError>),
+
+    #[error("Unable to open table: {0}")]
+    TableError(#[from] redb::TableError),
+
+    #[error("Unable to access storage: {0}")]
+    StorageError(#[from] redb::StorageError),
+
+    #[error("Unable to commit transaction: {0}")]
+    CommitError(#[from] redb::CommitError),
+
+    #[error("Unable to serialize or deserialize label: {0}")]
+    SerdeError(#[from] serde_json::Error),
+}
+
+#[cfg(feature = "redb")]
+impl From<redb::TransactionError> for RepositoryError {
+    fn from(error: redb::TransactionError) -> Self {
+        // boxed because it is much larger than the other variants
+        Self::TransactionError(Box::new(error))
+    }
+}
+
+/// Errors that can occur when reading, writing or editing a label journal.
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    #[error("Unable to read or write journal file: {0}")]
+    FileError(#[from] std::io::Error),
+
+    #[error("Invalid journal entry on line {line}: {source}")]
+    InvalidEntry {
+        /// The 1-based line number of the invalid entry
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("Unable to serialize journal entry: {0}")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("No label to change for {0}")]
+    MissingLabel(String),
+}
+
+/// Errors that can occur when editing labels in an edit session.
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("No label to change for {0}")]
+    MissingLabel(String),
+
+    #[error("No transaction is open")]
+    NoTransaction,
+}
+
+/// Errors that can occur when starting the label sync server.
+#[cfg(feature = "server")]
+#[derive(Debug, thiserror::Error)]
+pub enum ServerError {
+    #[error("Unable to listen for connections: {0}")]
+    BindError(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Unable to read the label store: {0}")]
+    StoreError(#[from] std::io::Error),
+}
+
+/// Errors that can occur when syncing labels with a label sync server.
+#[cfg(feature = "client")]
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Unable to reach the label server: {0}")]
+    HttpError(#[from] ureq::Error),
+
+    #[error("The labels on the server changed, the current version is {current:?}")]
+    VersionConflict {
+        /// The version on the server, `None` if the wallet has no labels
+        current: Option<u64>,
+    },
+
+    #[error("Invalid wallet id {0:?}, use 1 to 64 ASCII letters, digits, '-' or '_'")]
+    InvalidWalletId(String),
+
+    #[error("The label server rejected the access token")]
+    Unauthorized,
+
+    #[error("Unexpected response from the label server, status {status}: {message}")]
+    UnexpectedResponse { status: u16, message: String },
+
+    #[error("Invalid encrypted labels from the label server: {0}")]
+    EncryptionError(#[from] EncryptionError),
 }
@@ -0,0 +1,182 @@
+//! Foreign language bindings, behind the `uniffi` feature.
+//!
+//! [`Labels`] and [`EncryptedLabels`](crate::encryption::EncryptedLabels) are exported
+//! as objects, labels and record refs as enums, and records as records. Txids,
+//! outpoints and addresses are passed as strings and parsed when lifted.
+
+use bitcoin::{address::NetworkUnchecked, Address, OutPoint, Txid};
+use uniffi::deps::anyhow::anyhow;
+
+use crate::{
+    error::{ExportError, ParseError},
+    Label, LabelParseOptions, LabelRef, Labels,
+};
+
+type UncheckedAddress = Address<NetworkUnchecked>;
+
+uniffi::custom_type!(Txid, String, {
+    remote,
+    try_lift: |txid| txid.parse().map_err(|error| anyhow!("Invalid txid: {error}")),
+    lower: |txid| txid.to_string(),
+});
+
+uniffi::custom_type!(OutPoint, String, {
+    remote,
+    try_lift: |outpoint| outpoint.parse().map_err(|error| anyhow!("Invalid outpoint: {error}")),
+    lower: |outpoint| outpoint.to_string(),
+});
+
+uniffi::custom_type!(UncheckedAddress, String, {
+    remote,
+    try_lift: |address| address.parse().map_err(|error| anyhow!("Invalid address: {error}")),
+    lower: |address| address.assume_checked().to_string(),
+});
+
+#[uniffi::export]
+impl Labels {
+    /// Create a new Labels struct from a list of labels.
+    #[uniffi::constructor(name = "new")]
+    fn ffi_new(labels: Vec<Label>) -> Self {
+        Self::new(labels)
+    }
+
+    /// Create a new Labels struct from a JSONL string.
+    #[uniffi::constructor(name = "try_from_str")]
+    fn ffi_try_from_str(labels: String) -> Result<Self, ParseError> {
+        Self::try_from_str(&labels)
+    }
+
+    /// Create a new Labels struct from a JSONL string, with the given parse options.
+    #[uniffi::constructor(name = "try_from_str_with_options")]
+    fn ffi_try_from_str_with_options(
+        labels: String,
+        options: LabelParseOptions,
+    ) -> Result<Self, ParseError> {
+        Self::try_from_str_with_options(&labels, options)
+    }
+
+    /// Create a new Labels struct from a JSONL file.
+    #[uniffi::constructor(name = "try_from_file")]
+    fn ffi_try_from_file(path: String) -> Result<Self, ParseError> {
+        Self::try_from_file(path)
+    }
+
+    /// Export the labels to a JSONL string.
+    #[uniffi::method(name = "export")]
+    fn ffi_export(&self) -> Result<String, ExportError> {
+        self.export()
+    }
+
+    /// Export the labels to a JSONL file.
+    #[uniffi::method(name = "export_to_file")]
+    fn ffi_export_to_file(&self, path: String) -> Result<(), ExportError> {
+        self.export_to_file(path)
+    }
+
+    /// A copy of the labels.
+    #[uniffi::method(name = "labels")]
+    fn ffi_labels(&self) -> Vec<Label> {
+        self.0.clone()
+    }
+
+    /// The number of labels.
+    #[uniffi::method(name = "len")]
+    fn ffi_len(&self) -> u64 {
+        self.len() as u64
+    }
+
+    /// Returns true if there are no labels.
+    #[uniffi::method(name = "is_empty")]
+    fn ffi_is_empty(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+/// The ref of the label.
+#[uniffi::export]
+fn label_ref(label: Label) -> LabelRef {
+    label.ref_()
+}
+
+/// The ref as it is written in BIP329 JSONL.
+#[uniffi::export]
+fn label_ref_to_string(ref_: LabelRef) -> String {
+    ref_.to_string()
+}
+
+/// Create the default parse options, which reject unsupported record types.
+#[uniffi::export]
+fn default_label_parse_options() -> LabelParseOptions {
+    LabelParseOptions::default()
+}
+
+#[cfg(feature = "encryption")]
+mod encryption {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{encryption::EncryptedLabels, error::EncryptionError};
+
+    #[uniffi::export]
+    impl EncryptedLabels {
+        /// Encrypt the labels using the given passphrase.
+        #[uniffi::constructor(name = "encrypt")]
+        fn ffi_encrypt(labels: Arc<Labels>, passphrase: String) -> Result<Self, EncryptionError> {
+            Self::encrypt(&labels, &passphrase)
+        }
+
+        /// Create a new EncryptedLabels struct from a hex string.
+        #[uniffi::constructor(name = "from_hex")]
+        fn ffi_from_hex(hex: String) -> Result<Self, EncryptionError> {
+            Self::from_hex(&hex)
+        }
+
+        /// Create a new EncryptedLabels struct from an ASCII armored string.
+        #[uniffi::constructor(name = "from_armor")]
+        fn ffi_from_armor(armor: String) -> Result<Self, EncryptionError> {
+            Self::from_armor(&armor)
+        }
+
+        /// Create a new EncryptedLabels struct from binary, ASCII armored or hex encoded bytes.
+        #[uniffi::constructor(name = "from_encoded")]
+        fn ffi_from_encoded(encoded: Vec<u8>) -> Result<Self, EncryptionError> {
+            Self::from_encoded(encoded)
+        }
+
+        /// Create a new EncryptedLabels struct from a file.
+        #[uniffi::constructor(name = "read_from_file")]
+        fn ffi_read_from_file(path: String) -> Result<Self, EncryptionError> {
+            Self::read_from_file(path)
+        }
+
+        /// Decrypt the labels using the given passphrase.
+        #[uniffi::method(name = "decrypt")]
+        fn ffi_decrypt(&self, passphrase: String) -> Result<Arc<Labels>, EncryptionError> {
+            self.decrypt(&passphrase).map(Arc::new)
+        }
+
+        /// Encode the encrypted labels as a hex string.
+        #[uniffi::method(name = "to_hex")]
+        fn ffi_to_hex(&self) -> Result<String, EncryptionError> {
+            self.to_hex()
+        }
+
+        /// Encode the encrypted labels as an ASCII armored string.
+        #[uniffi::method(name = "to_armor")]
+        fn ffi_to_armor(&self) -> Result<String, EncryptionError> {
+            self.to_armor()
+        }
+
+        /// The encrypted bytes.
+        #[uniffi::method(name = "to_bytes")]
+        fn ffi_to_bytes(&self) -> Vec<u8> {
+            self.clone().into_bytes()
+        }
+
+        /// Write the encrypted labels to a file.
+        #[uniffi::method(name = "write_to_file")]
+        fn ffi_write_to_file(&self, path: String) -> Result<(), EncryptionError> {
+            self.write_to_file(path)
+        }
+    }
+}
@@ -0,0 +1,769 @@
+//! Module for an append-only journal of label changes.
+//!
+//! [`LabelJournal`] wraps a [`Labels`] snapshot and records every change to it as a
+//! [`JournalEntry`], with the time of the change and an optional author, giving an
+//! audit trail of who changed which label and when. The journal is JSONL, one entry
+//! per line, and replaying it from the start rebuilds the snapshot:
+//!
+//! ```text
+//! {"time":1760000000,"author":"alice","op":"set-label","type":"tx","ref":"f91d…","label":"Rent"}
+//! {"time":1760000060,"author":"bob","op":"set-spendable","ref":"f91d…:1","spendable":false}
+//! {"time":1760000120,"op":"delete","type":"addr","ref":"bc1q…"}
+//! ```
+//!
+//! A journal opened with [`LabelJournal::open`] appends each entry to its file as the
+//! change is made. [`LabelJournal::compact`] replaces the history with one `insert` per
+//! label, keeping when and by whom each label was last changed.
+//!
+//! ```rust
+//! use bip329::{journal::LabelJournal, Labels};
+//!
+//! let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+//! let mut journal = LabelJournal::from_labels(labels).with_author("alice");
+//!
+//! let ref_ = journal.labels()[0].ref_();
+//! journal.set_label(ref_.clone(), "Rent").unwrap();
+//! journal.delete(ref_.clone()).unwrap();
+//!
+//! let history: Vec<_> = journal.history(&ref_).collect();
+//! assert_eq!(history.len(), 3);
+//! assert_eq!(LabelJournal::replay(journal.entries()), *journal.labels());
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bitcoin::OutPoint;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::JournalError, Label, LabelRef, Labels};
+
+/// A change to a single label record
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum Operation {
+    /// Insert or replace a whole record
+    Insert(Label),
+    /// Set the label of a record, creating the record if needed
+    SetLabel { ref_: LabelRef, label: String },
+    /// Remove the label from a record, keeping the record
+    ClearLabel { ref_: LabelRef },
+    /// Set whether an output is spendable, creating the record if needed
+    SetSpendable { ref_: OutPoint, spendable: bool },
+    /// Delete a record
+    Delete { ref_: LabelRef },
+}
+
+impl Operation {
+    /// The reference of the record the operation changes.
+    pub fn ref_(&self) -> LabelRef {
+        match self {
+            Self::Insert(label) => label.ref_(),
+            Self::SetLabel { ref_, .. } | Self::ClearLabel { ref_ } | Self::Delete { ref_ } => {
+                ref_.clone()
+            }
+            Self::SetSpendable { ref_, .. } => LabelRef::Output(*ref_),
+        }
+    }
+
+    /// Apply the operation, doing nothing if the record to change does not exist.
+    fn apply(&self, labels: &mut Labels) {
+        let ref_ = self.ref_();
+        let position = labels.iter().position(|label| label.ref_() == ref_);
+
+        match (self, position) {
+            (Self::Insert(label), Some(position)) => labels[position] = label.clone(),
+            (Self::Insert(label), None) => labels.push(label.clone()),
+            (Self::SetLabel { label, .. }, position) => {
+                let record = existing_or_new(labels, position, ref_);
+                *record.label_mut() = Some(label.clone());
+            }
+            (Self::SetSpendable { spendable, .. }, position) => {
+                if let Label::Output(record) = existing_or_new(labels, position, ref_) {
+                    record.spendable = Some(*spendable);
+                }
+            }
+            (Self::ClearLabel { .. }, Some(position)) => *labels[position].label_mut() = None,
+            (Self::Delete { .. }, Some(position)) => {
+                labels.remove(position);
+            }
+            (Self::ClearLabel { .. } | Self::Delete { .. }, None) => {}
+        }
+    }
+}
+
+fn existing_or_new(labels: &mut Labels, position: Option<usize>, ref_: LabelRef) -> &mut Label {
+    let position = position.unwrap_or_else(|| {
+        labels.push(Label::from_ref(ref_));
+        labels.len() - 1
+    });
+
+    &mut labels[position]
+}
+
+/// A recorded change
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "EntryLine", try_from = "EntryLine")]
+pub struct JournalEntry {
+    /// When the change was made, in seconds since the Unix epoch
+    pub time: u64,
+    /// Who made the change
+    pub author: Option<String>,
+    /// The change
+    pub operation: Operation,
+}
+
+impl JournalEntry {
+    /// Create an entry for a change made now.
+    pub fn now(operation: Operation, author: Option<String>) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            time,
+            author,
+            operation,
+        }
+    }
+}
+
+/// The JSONL form of an entry, with refs as BIP329 `type` and `ref` fields
+#[derive(Clone, Serialize, Deserialize)]
+struct EntryLine {
+    time: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(flatten)]
+    operation: OperationLine,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+enum OperationLine {
+    Insert {
+        record: Label,
+    },
+    SetLabel {
+        #[serde(flatten)]
+        ref_: RefLine,
+        label: String,
+    },
+    ClearLabel {
+        #[serde(flatten)]
+        ref_: RefLine,
+    },
+    SetSpendable {
+        #[serde(rename = "ref")]
+        ref_: OutPoint,
+        spendable: bool,
+    },
+    Delete {
+        #[serde(flatten)]
+        ref_: RefLine,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct RefLine {
+    #[serde(rename = "type")]
+    type_: String,
+    #[serde(rename = "ref")]
+    ref_: String,
+}
+
+impl From<LabelRef> for RefLine {
+    fn from(ref_: LabelRef) -> Self {
+        Self {
+            type_: ref_.type_tag().to_string(),
+            ref_: ref_.to_string(),
+        }
+    }
+}
+
+impl TryFrom<RefLine> for LabelRef {
+    type Error = serde_json::Error;
+
+    fn try_from(line: RefLine) -> Result<Self, Self::Error> {
+        // parsed as an empty record, so refs are validated the same way as in JSONL
+        let record = serde_json::json!({ "type": line.type_, "ref": line.ref_ });
+        serde_json::from_value::<Label>(record).map(|label| label.ref_())
+    }
+}
+
+impl From<JournalEntry> for EntryLine {
+    fn from(entry: JournalEntry) -> Self {
+        let operation = match entry.operation {
+            Operation::Insert(record) => OperationLine::Insert { record },
+            Operation::SetLabel { ref_, label } => OperationLine::SetLabel {
+                ref_: ref_.into(),
+                label,
+            },
+            Operation::ClearLabel { ref_ } => OperationLine::ClearLabel { ref_: ref_.into() },
+            Operation::SetSpendable { ref_, spendable } => {
+                OperationLine::SetSpendable { ref_, spendable }
+            }
+            Operation::Delete { ref_ } => OperationLine::Delete { ref_: ref_.into() },
+        };
+
+        Self {
+            time: entry.time,
+            author: entry.author,
+            operation,
+        }
+    }
+}
+
+impl TryFrom<EntryLine> for JournalEntry {
+    type Error = serde_json::Error;
+
+    fn try_from(line: EntryLine) -> Result<Self, Self::Error> {
+        let operation = match line.operation {
+            OperationLine::Insert { record } => Operation::Insert(record),
+            OperationLine::SetLabel { ref_, label } => Operation::SetLabel {
+                ref_: ref_.try_into()?,
+                label,
+            },
+            OperationLine::ClearLabel { ref_ } => Operation::ClearLabel {
+                ref_: ref_.try_into()?,
+            },
+            OperationLine::SetSpendable { ref_, spendable } => {
+                Operation::SetSpendable { ref_, spendable }
+            }
+            OperationLine::Delete { ref_ } => Operation::Delete {
+                ref_: ref_.try_into()?,
+            },
+        };
+
+        Ok(Self {
+            time: line.time,
+            author: line.author,
+            operation,
+        })
+    }
+}
+
+/// Labels with a journal of every change, see the [module docs](self).
+#[derive(Debug, Default)]
+pub struct LabelJournal {
+    labels: Labels,
+    entries: Vec<JournalEntry>,
+    author: Option<String>,
+    file: Option<(PathBuf, File)>,
+}
+
+impl LabelJournal {
+    /// Create an empty journal, kept in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a journal starting with an `insert` for each label, kept in memory.
+    pub fn from_labels(labels: Labels) -> Self {
+        let entries = labels
+            .iter()
+            .map(|label| JournalEntry::now(Operation::Insert(label.clone()), None))
+            .collect();
+
+        Self {
+            labels,
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// Parse a JSONL journal and replay it, kept in memory.
+    pub fn try_from_str(jsonl: &str) -> Result<Self, JournalError> {
+        let entries = parse_entries(jsonl)?;
+        let labels = Self::replay(&entries);
+
+        Ok(Self {
+            labels,
+            entries,
+            ..Self::default()
+        })
+    }
+
+    /// Open a journal file, creating it if it does not exist, and replay it.
+    ///
+    /// Changes are appended to the file as they are made. A last line without a newline,
+    /// left by an interrupted append, is dropped from the file if it isn't a valid entry.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, JournalError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let mut jsonl = std::fs::read_to_string(&path)?;
+
+        if !jsonl.is_empty() && !jsonl.ends_with('\n') {
+            let start = jsonl.rfind('\n').map_or(0, |index| index + 1);
+
+            if serde_json::from_str::<JournalEntry>(&jsonl[start..]).is_ok() {
+                jsonl.push('\n');
+                file.write_all(b"\n")?;
+            } else {
+                jsonl.truncate(start);
+                file.set_len(start as u64)?;
+            }
+        }
+
+        let mut journal = Self::try_from_str(&jsonl)?;
+        journal.file = Some((path, file));
+        Ok(journal)
+    }
+
+    /// Set the author recorded with changes.
+    #[must_use]
+    pub fn with_author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    /// The labels after every change.
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    /// The recorded changes, oldest first.
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// The recorded changes to a single record, oldest first.
+    pub fn history<'a>(&'a self, ref_: &'a LabelRef) -> impl Iterator<Item = &'a JournalEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.operation.ref_() == *ref_)
+    }
+
+    /// Rebuild the labels by applying the entries in order.
+    ///
+    /// Entries that change a record that does not exist are skipped.
+    pub fn replay(entries: &[JournalEntry]) -> Labels {
+        let mut labels = Labels::default();
+        for entry in entries {
+            entry.operation.apply(&mut labels);
+        }
+
+        labels
+    }
+
+    /// The labels as they were at the given time, in seconds since the Unix epoch.
+    ///
+    /// Entries are not necessarily in time order, e.g. after [`LabelJournal::compact`]
+    /// or when appended from another journal, so every entry up to the time is replayed.
+    /// After compaction, a label changed after the time is missing from the result.
+    pub fn labels_at(&self, time: u64) -> Labels {
+        let mut labels = Labels::default();
+        for entry in self.entries.iter().filter(|entry| entry.time <= time) {
+            entry.operation.apply(&mut labels);
+        }
+
+        labels
+    }
+
+    /// Insert or replace a whole record.
+    pub fn insert(&mut self, label: Label) -> Result<(), JournalError> {
+        self.apply(Operation::Insert(label))
+    }
+
+    /// Set the label of a record, creating the record if needed.
+    pub fn set_label(
+        &mut self,
+        ref_: LabelRef,
+        label: impl Into<String>,
+    ) -> Result<(), JournalError> {
+        let label = label.into();
+        self.apply(Operation::SetLabel { ref_, label })
+    }
+
+    /// Remove the label from a record, keeping the record.
+    pub fn clear_label(&mut self, ref_: LabelRef) -> Result<(), JournalError> {
+        self.apply(Operation::ClearLabel { ref_ })
+    }
+
+    /// Set whether an output is spendable, creating the record if needed.
+    pub fn set_spendable(&mut self, ref_: OutPoint, spendable: bool) -> Result<(), JournalError> {
+        self.apply(Operation::SetSpendable { ref_, spendable })
+    }
+
+    /// Delete a record.
+    pub fn delete(&mut self, ref_: LabelRef) -> Result<(), JournalError> {
+        self.apply(Operation::Delete { ref_ })
+    }
+
+    /// Record and apply a change made now by the journal's author.
+    pub fn apply(&mut self, operation: Operation) -> Result<(), JournalError> {
+        self.append(JournalEntry::now(operation, self.author.clone()))
+    }
+
+    /// Record and apply an entry, e.g. one copied from another journal.
+    ///
+    /// Fails without recording the entry if it changes a record that does not exist,
+    /// or if it can't be written to the journal file.
+    pub fn append(&mut self, entry: JournalEntry) -> Result<(), JournalError> {
+        let ref_ = entry.operation.ref_();
+        let exists = self.labels.iter().any(|label| label.ref_() == ref_);
+
+        if let (Operation::ClearLabel { .. } | Operation::Delete { .. }, false) =
+            (&entry.operation, exists)
+        {
+            let ref_ = format!("{} {ref_}", ref_.type_tag());
+            return Err(JournalError::MissingLabel(ref_));
+        }
+
+        if let Some((_, file)) = &mut self.file {
+            // a single write, so an interrupted append can't leave a line without its newline
+            let line = serde_json::to_string(&entry)? + "\n";
+            file.write_all(line.as_bytes())?;
+            file.flush()?;
+        }
+
+        entry.operation.apply(&mut self.labels);
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Replace the entries with an `insert` for each label, with the time and author
+    /// of the label's last change.
+    ///
+    /// A journal file is rewritten atomically.
+    pub fn compact(&mut self) -> Result<(), JournalError> {
+        let last_changes: HashMap<_, _> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.operation.ref_(), entry))
+            .collect();
+
+        let entries = self
+            .labels
+            .iter()
+            .map(|label| {
+                let last_change = last_changes.get(&label.ref_());
+
+                JournalEntry {
+                    time: last_change.map_or(0, |entry| entry.time),
+                    author: last_change.and_then(|entry| entry.author.clone()),
+                    operation: Operation::Insert(label.clone()),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if let Some((path, file)) = &mut self.file {
+            let mut temp_path = path.clone().into_os_string();
+            temp_path.push(".tmp");
+
+            std::fs::write(&temp_path, export_entries(&entries)?)?;
+            std::fs::rename(&temp_path, &path)?;
+            *file = OpenOptions::new().append(true).open(&path)?;
+        }
+
+        self.entries = entries;
+        Ok(())
+    }
+
+    /// Export the journal as JSONL.
+    pub fn export(&self) -> Result<String, JournalError> {
+        export_entries(&self.entries)
+    }
+
+    /// Export the journal to a JSONL file.
+    pub fn export_to_file(&self, path: impl AsRef<Path>) -> Result<(), JournalError> {
+        std::fs::write(path, self.export()?)?;
+        Ok(())
+    }
+}
+
+fn parse_entries(jsonl: &str) -> Result<Vec<JournalEntry>, JournalError> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|source| JournalError::InvalidEntry {
+                line: index + 1,
+                source,
+            })
+        })
+        .collect()
+}
+
+fn export_entries(entries: &[JournalEntry]) -> Result<String, JournalError> {
+    entries.iter().try_fold(String::new(), |mut acc, entry| {
+        acc.push_str(&serde_json::to_string(entry)?);
+        acc.push('\n');
+        Ok(acc)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const TXID: &str = "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd";
+
+    fn outpoint(vout: u32) -> OutPoint {
+        OutPoint::from_str(&format!("{TXID}:{vout}")).unwrap()
+    }
+
+    fn tx_ref() -> LabelRef {
+        LabelRef::Txid(bitcoin::Txid::from_str(TXID).unwrap())
+    }
+
+    fn entry(time: u64, author: &str, operation: Operation) -> JournalEntry {
+        JournalEntry {
+            time,
+            author: Some(author.to_string()),
+            operation,
+        }
+    }
+
+    #[test]
+    fn operations_change_the_snapshot() {
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let mut journal = LabelJournal::from_labels(labels.clone()).with_author("alice");
+
+        journal.set_label(tx_ref(), "Rent").unwrap();
+        journal.set_spendable(outpoint(1), true).unwrap();
+        journal.set_spendable(outpoint(9), false).unwrap();
+        journal.clear_label(LabelRef::Input(outpoint(0))).unwrap();
+        journal.delete(labels[1].ref_()).unwrap();
+
+        let snapshot = journal.labels();
+        assert_eq!(snapshot.len(), labels.len());
+        assert_eq!(snapshot[0].label(), Some("Rent"));
+        assert_eq!(snapshot[2].ref_(), LabelRef::Input(outpoint(0)));
+        assert_eq!(snapshot[2].label(), None);
+
+        let Label::Output(output) = &snapshot[3] else {
+            panic!("expected an output label");
+        };
+        assert_eq!(output.spendable, Some(true));
+        assert_eq!(output.label.as_deref(), Some("Output"));
+
+        let Label::Output(new_output) = &snapshot[snapshot.len() - 1] else {
+            panic!("expected a new output label");
+        };
+        assert_eq!(new_output.ref_, outpoint(9));
+        assert_eq!(new_output.label, None);
+        assert_eq!(new_output.spendable, Some(false));
+
+        let ref_ = tx_ref();
+        let changes = journal.history(&ref_).collect::<Vec<_>>();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].author.as_deref(), Some("alice"));
+        assert_eq!(changes[0].author, None);
+    }
+
+    #[test]
+    fn changes_to_missing_labels_are_not_recorded() {
+        let mut journal = LabelJournal::new();
+        let ref_ = LabelRef::Output(outpoint(1));
+
+        let error = journal.delete(ref_.clone()).unwrap_err();
+        assert!(matches!(error, JournalError::MissingLabel(ref_) if ref_.starts_with("output ")));
+        assert!(journal.clear_label(ref_).is_err());
+        assert!(journal.entries().is_empty());
+    }
+
+    #[test]
+    fn journal_round_trips_and_replays() {
+        let mut journal = LabelJournal::new();
+        journal
+            .append(entry(
+                10,
+                "alice",
+                Operation::SetLabel {
+                    ref_: tx_ref(),
+                    label: "Rent".into(),
+                },
+            ))
+            .unwrap();
+        journal
+            .append(entry(
+                20,
+                "bob",
+                Operation::SetSpendable {
+                    ref_: outpoint(1),
+                    spendable: false,
+                },
+            ))
+            .unwrap();
+        journal
+            .append(entry(30, "alice", Operation::Delete { ref_: tx_ref() }))
+            .unwrap();
+
+        let jsonl = journal.export().unwrap();
+        let first = jsonl.lines().next().unwrap();
+        assert_eq!(
+            first,
+            format!(r#"{{"time":10,"author":"alice","op":"set-label","type":"tx","ref":"{TXID}","#)
+                + r#""label":"Rent"}"#
+        );
+
+        let loaded = LabelJournal::try_from_str(&jsonl).unwrap();
+        assert_eq!(loaded.entries(), journal.entries());
+        assert_eq!(loaded.labels(), journal.labels());
+        assert_eq!(loaded.labels().len(), 1);
+
+        assert_eq!(loaded.labels_at(5).len(), 0);
+        assert_eq!(loaded.labels_at(20).len(), 2);
+        assert_eq!(loaded.labels_at(20)[0].label(), Some("Rent"));
+    }
+
+    #[test]
+    fn invalid_entries_report_the_line() {
+        let jsonl = format!(
+            "{{\"time\":1,\"op\":\"delete\",\"type\":\"tx\",\"ref\":\"{TXID}\"}}\n\
+             {{\"time\":2,\"op\":\"delete\",\"type\":\"tx\",\"ref\":\"not a txid\"}}"
+        );
+
+        let error = LabelJournal::try_from_str(&jsonl).unwrap_err();
+        assert!(matches!(error, JournalError::InvalidEntry { line: 2, .. }));
+    }
+
+    #[test]
+    fn compact_keeps_the_last_change() {
+        let mut journal = LabelJournal::new();
+        journal
+            .append(entry(
+                10,
+                "alice",
+                Operation::SetLabel {
+                    ref_: tx_ref(),
+                    label: "Rent".into(),
+                },
+            ))
+            .unwrap();
+        journal
+            .append(entry(
+                20,
+                "bob",
+                Operation::SetLabel {
+                    ref_: tx_ref(),
+                    label: "Bills".into(),
+                },
+            ))
+            .unwrap();
+        journal
+            .append(entry(
+                30,
+                "bob",
+                Operation::SetSpendable {
+                    ref_: outpoint(1),
+                    spendable: false,
+                },
+            ))
+            .unwrap();
+        journal
+            .append(entry(
+                40,
+                "carol",
+                Operation::Delete {
+                    ref_: LabelRef::Output(outpoint(1)),
+                },
+            ))
+            .unwrap();
+
+        let labels = journal.labels().clone();
+        journal.compact().unwrap();
+
+        assert_eq!(journal.labels(), &labels);
+        assert_eq!(LabelJournal::replay(journal.entries()), labels);
+        assert_eq!(journal.entries().len(), 1);
+        assert_eq!(journal.entries()[0].time, 20);
+        assert_eq!(journal.entries()[0].author.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn labels_at_after_compact() {
+        let output = Label::from_ref(LabelRef::Output(outpoint(1)));
+
+        let mut journal = LabelJournal::new();
+        journal
+            .append(entry(
+                10,
+                "alice",
+                Operation::Insert(Label::from_ref(tx_ref())),
+            ))
+            .unwrap();
+        journal
+            .append(entry(20, "alice", Operation::Insert(output.clone())))
+            .unwrap();
+        journal
+            .append(entry(
+                30,
+                "bob",
+                Operation::SetLabel {
+                    ref_: tx_ref(),
+                    label: "Rent".into(),
+                },
+            ))
+            .unwrap();
+
+        journal.compact().unwrap();
+        assert_eq!(journal.entries()[0].time, 30);
+
+        assert_eq!(journal.labels_at(25), Labels::new(vec![output]));
+        assert_eq!(journal.labels_at(30), *journal.labels());
+    }
+
+    #[test]
+    fn journal_file_is_appended_and_compacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("labels.journal");
+
+        let mut journal = LabelJournal::open(&path).unwrap().with_author("alice");
+        journal.set_label(tx_ref(), "Rent").unwrap();
+        journal.set_label(tx_ref(), "Bills").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        let mut reopened = LabelJournal::open(&path).unwrap();
+        assert_eq!(reopened.labels(), journal.labels());
+        assert_eq!(reopened.entries(), journal.entries());
+
+        reopened.compact().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        reopened.set_spendable(outpoint(1), false).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert_eq!(
+            LabelJournal::try_from_str(&contents).unwrap().labels(),
+            reopened.labels()
+        );
+    }
+
+    #[test]
+    fn interrupted_append_is_dropped_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("labels.journal");
+
+        let mut journal = LabelJournal::open(&path).unwrap();
+        journal.set_label(tx_ref(), "Rent").unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+
+        // a partial entry is truncated, and appends continue on a new line
+        std::fs::write(&path, format!("{contents}{{\"time\":1,\"op")).unwrap();
+        let mut reopened = LabelJournal::open(&path).unwrap();
+        assert_eq!(reopened.entries(), journal.entries());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+
+        reopened.set_spendable(outpoint(1), false).unwrap();
+        assert_eq!(LabelJournal::open(&path).unwrap().entries().len(), 2);
+
+        // a complete entry missing only its newline is kept
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.trim_end()).unwrap();
+        let mut reopened = LabelJournal::open(&path).unwrap();
+        reopened.delete(tx_ref()).unwrap();
+        assert_eq!(LabelJournal::open(&path).unwrap().entries().len(), 3);
+    }
+}
@@ -1,8 +1,8 @@
 use crate::{
     error::{ExportError, ParseError},
-    AddressRecord, ExtendedPublicKeyRecord, InputRecord, Label, LabelParseOptions, LabelRef,
-    Labels, OutputRecord, OutputSpendableField, ParsedLabels, PublicKeyRecord,
-    SilentPaymentsScanRecord, SpendableFieldValue, TransactionRecord,
+    is_known_label_type, AddressRecord, ExtendedPublicKeyRecord, InputRecord, Label,
+    LabelParseOptions, LabelRef, Labels, OutputRecord, OutputSpendableField, ParsedLabels,
+    PublicKeyRecord, SilentPaymentsScanRecord, SpendableFieldValue, TransactionRecord,
 };
 use std::{
     collections::HashMap,
@@ -37,8 +37,13 @@ impl Labels {
     ) -> Result<Self, ParseError> {
         let mut parsed_labels = Vec::new();
 
-        for line in labels.trim().lines() {
-            if let Some(label) = parse_label_line(line, options)? {
+        for (index, line) in labels.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let label = parse_label_line(line, options).map_err(|e| e.at_line(index + 1))?;
+            if let Some(label) = label {
                 parsed_labels.push(label);
             }
         }
@@ -65,8 +70,14 @@ impl Labels {
         let mut output_spendable = Vec::new();
         let mut parsed_labels = Vec::new();
 
-        for line in labels.trim().lines() {
-            let Some(line) = parse_label_line_with_metadata(line, options)? else {
+        for (index, line) in labels.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let line =
+                parse_label_line_with_metadata(line, options).map_err(|e| e.at_line(index + 1))?;
+            let Some(line) = line else {
                 continue;
             };
             let (label, spendable) = line.into_label_and_spendable();
@@ -89,6 +100,9 @@ impl Labels {
     }
 
     /// Create labels from a file using custom parse options.
+    ///
+    /// With the `gzip` or `zstd` feature enabled, compressed files are detected
+    /// by their magic bytes and decompressed while reading.
     pub fn try_from_file_with_options(
         path: impl AsRef<Path>,
         options: LabelParseOptions,
@@ -96,11 +110,19 @@ impl Labels {
         let file = File::open(path.as_ref())?;
         let buffer_reader = BufReader::new(file);
 
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        let buffer_reader = crate::compression::decompressing_reader(buffer_reader)?;
+
         let mut labels = Vec::new();
 
-        for line in buffer_reader.lines() {
+        for (index, line) in buffer_reader.lines().enumerate() {
             let line = line.map_err(ParseError::FileReadError)?;
-            if let Some(label) = parse_label_line(&line, options)? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let label = parse_label_line(&line, options).map_err(|e| e.at_line(index + 1))?;
+            if let Some(label) = label {
                 labels.push(label);
             }
         }
@@ -152,17 +174,20 @@ impl Labels {
     /// Export the Labels struct to a writer.
     pub fn export_to_writer<W: std::io::Write>(&self, mut writer: W) -> Result<(), ExportError> {
         self.0.iter().try_for_each(|label: &Label| {
-            let label = serde_json::to_string(label)?;
-            writer.write_all(label.as_bytes())?;
+            serde_json::to_writer(&mut writer, label)?;
             writer.write_all(b"\n")?;
             Ok(())
         })
     }
 
     /// Get the inner Vec of the Labels struct.
+    ///
+    /// With the `zeroize` feature, the labels are moved out without being cleared, and
+    /// the returned Vec is not cleared when dropped. Wrap it in `zeroize::Zeroizing`
+    /// to keep clearing it.
     #[must_use]
-    pub fn into_vec(self) -> Vec<Label> {
-        self.0
+    pub fn into_vec(mut self) -> Vec<Label> {
+        std::mem::take(&mut self.0)
     }
 
     /// Get the inner Vec of the Labels struct converted to a HashMap
@@ -216,13 +241,6 @@ fn should_skip_unknown_label_type(
     Ok(!is_known_label_type(&label_type.type_))
 }
 
-fn is_known_label_type(label_type: &str) -> bool {
-    matches!(
-        label_type,
-        "tx" | "addr" | "pubkey" | "input" | "output" | "xpub" | "spscan"
-    )
-}
-
 #[derive(serde::Deserialize)]
 struct LabelType {
     #[serde(rename = "type")]
@@ -307,6 +325,44 @@ impl Label {
         }
     }
 
+    /// An empty record for the reference, with no label or other fields
+    pub(crate) fn from_ref(ref_: LabelRef) -> Self {
+        match ref_ {
+            LabelRef::Txid(ref_) => Label::Transaction(TransactionRecord {
+                ref_,
+                label: None,
+                origin: None,
+            }),
+            LabelRef::Address(ref_) => Label::Address(AddressRecord { ref_, label: None }),
+            LabelRef::PublicKey(ref_) => Label::PublicKey(PublicKeyRecord { ref_, label: None }),
+            LabelRef::Input(ref_) => Label::Input(InputRecord { ref_, label: None }),
+            LabelRef::Output(ref_) => Label::Output(OutputRecord {
+                ref_,
+                label: None,
+                spendable: None,
+            }),
+            LabelRef::Xpub(ref_) => {
+                Label::ExtendedPublicKey(ExtendedPublicKeyRecord { ref_, label: None })
+            }
+            LabelRef::SilentPaymentsScan(ref_) => {
+                Label::SilentPaymentsScan(SilentPaymentsScanRecord { ref_, label: None })
+            }
+        }
+    }
+
+    /// The `label` field of the record, for editing
+    pub fn label_mut(&mut self) -> &mut Option<String> {
+        match self {
+            Label::Transaction(record) => &mut record.label,
+            Label::Address(record) => &mut record.label,
+            Label::PublicKey(record) => &mut record.label,
+            Label::Input(record) => &mut record.label,
+            Label::Output(record) => &mut record.label,
+            Label::ExtendedPublicKey(record) => &mut record.label,
+            Label::SilentPaymentsScan(record) => &mut record.label,
+        }
+    }
+
     /// Get the reference of the label as a &str
     pub fn ref_(&self) -> LabelRef {
         match self {
@@ -341,9 +397,10 @@ impl From<Vec<Label>> for Labels {
     }
 }
 
+/// Moves the labels out without clearing them, see [`Labels::into_vec`]
 impl From<Labels> for Vec<Label> {
     fn from(value: Labels) -> Self {
-        value.0
+        value.into_vec()
     }
 }
 
@@ -609,6 +666,31 @@ mod tests {
         assert_eq!(record.label.as_deref(), Some("Transaction"));
     }
 
+    #[test]
+    fn blank_lines_are_skipped() {
+        let jsonl = r#"
+{"type": "tx", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd", "label": "Transaction"}
+
+{"type": "addr", "ref": "bc1q34aq5drpuwy3wgl9lhup9892qp6svr8ldzyy7c", "label": "Address"}
+"#;
+
+        let labels = Labels::try_from_str(jsonl).unwrap();
+        assert_eq!(labels.len(), 2);
+
+        let invalid = format!("{jsonl}\n{{\"type\": \"tx\"}}");
+        let error = Labels::try_from_str(&invalid).unwrap_err();
+        assert_eq!(error.line(), Some(6));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("labels.jsonl");
+        std::fs::write(&path, jsonl).unwrap();
+        assert_eq!(Labels::try_from_file(&path).unwrap().len(), 2);
+
+        std::fs::write(&path, invalid).unwrap();
+        let error = Labels::try_from_file(&path).unwrap_err();
+        assert_eq!(error.line(), Some(6));
+    }
+
     #[test]
     fn can_ignore_unknown_record_types() {
         let jsonl = r#"{"type": "tx", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd", "label": "Transaction"}
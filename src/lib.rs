@@ -4,7 +4,10 @@
 //! - The [`Label`](crate::Label) enum containing all the different types of labels.
 //! - The [`Labels`](crate::Labels) struct can be imported/exported to/from a JSONL file.
 //! - Supports encryption and decryption using the [`encryption`](crate::encryption) module.
-//! - Supports the [`uniffi`](https://github.com/mozilla/uniffi-rs) feature, for easy integration with other languages.
+//! - Supports the [`uniffi`](https://github.com/mozilla/uniffi-rs) feature, for Kotlin and Swift bindings.
+//! - Supports the [`python`](https://pyo3.rs) feature, for a Python module built with maturin.
+//! - Supports the `capi` feature, for a C ABI with a header in `include/bip329.h`.
+//! - Supports the `server` and `client` features, for self-hosted label sync with the [`remote`](crate::remote) module.
 //!
 //! ### Example Import:
 //! ```rust
@@ -47,20 +50,59 @@ pub mod error;
 #[cfg(feature = "encryption")]
 pub mod encryption;
 
+#[cfg(feature = "binary")]
+pub mod binary;
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub mod compression;
+
+#[cfg(feature = "signing")]
+pub mod signing;
+
+#[cfg(any(feature = "server", feature = "client"))]
+pub mod remote;
+
 pub mod from;
+pub mod journal;
+pub mod report;
+pub mod repository;
+pub mod session;
+pub mod sync;
+
+mod canonical;
 mod label;
 mod serde_util;
 
+#[cfg(feature = "uniffi")]
+mod ffi;
+
+#[cfg(feature = "capi")]
+mod capi;
+
+#[cfg(feature = "python")]
+mod python;
+
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(feature = "zeroize")]
+mod zeroizing;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
 use bitcoin::{address::NetworkUnchecked, Address};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 /// A list of labels.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Object), uniffi::export(Debug, Eq))]
 pub struct Labels(Vec<Label>);
 
 /// Options for importing BIP329 JSONL records
 #[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct LabelParseOptions {
     pub(crate) ignore_unknown_types: bool,
 }
@@ -147,6 +189,7 @@ impl<'de> Deserialize<'de> for SpendableFieldValue {
 
 /// The main data structure for BIP329 labels.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 #[serde(tag = "type")]
 pub enum Label {
     #[serde(rename = "tx")]
@@ -165,8 +208,17 @@ pub enum Label {
     SilentPaymentsScan(SilentPaymentsScanRecord),
 }
 
+/// All BIP329 `type` values, in the order of the specification
+pub const LABEL_TYPES: [&str; 7] = ["tx", "addr", "pubkey", "input", "output", "xpub", "spscan"];
+
+/// Whether the value is a BIP329 `type` this crate supports
+pub fn is_known_label_type(label_type: &str) -> bool {
+    LABEL_TYPES.contains(&label_type)
+}
+
 /// An enum representing all possible [`Label::ref_`]
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 pub enum LabelRef {
     Txid(bitcoin::Txid),
     Address(bitcoin::Address<NetworkUnchecked>),
@@ -177,6 +229,21 @@ pub enum LabelRef {
     SilentPaymentsScan(String),
 }
 
+impl LabelRef {
+    /// The BIP329 `type` value of the record this reference points to
+    pub fn type_tag(&self) -> &'static str {
+        match self {
+            LabelRef::Txid(_) => "tx",
+            LabelRef::Address(_) => "addr",
+            LabelRef::PublicKey(_) => "pubkey",
+            LabelRef::Input(_) => "input",
+            LabelRef::Output(_) => "output",
+            LabelRef::Xpub(_) => "xpub",
+            LabelRef::SilentPaymentsScan(_) => "spscan",
+        }
+    }
+}
+
 impl Display for LabelRef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
@@ -193,6 +260,7 @@ impl Display for LabelRef {
 
 /// A transaction label.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct TransactionRecord {
     #[serde(rename = "ref")]
     pub ref_: bitcoin::Txid,
@@ -204,6 +272,7 @@ pub struct TransactionRecord {
 
 /// An address label.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct AddressRecord {
     #[serde(rename = "ref")]
     pub ref_: Address<NetworkUnchecked>,
@@ -213,6 +282,7 @@ pub struct AddressRecord {
 
 /// A public key label.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct PublicKeyRecord {
     #[serde(rename = "ref")]
     pub ref_: String,
@@ -223,6 +293,7 @@ pub struct PublicKeyRecord {
 
 /// An input label.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct InputRecord {
     #[serde(rename = "ref")]
     pub ref_: bitcoin::OutPoint,
@@ -231,6 +302,7 @@ pub struct InputRecord {
 
 /// An output label.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct OutputRecord {
     #[serde(rename = "ref")]
     pub ref_: bitcoin::OutPoint,
@@ -247,6 +319,7 @@ pub struct OutputRecord {
 
 /// An extended public key label.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct ExtendedPublicKeyRecord {
     #[serde(rename = "ref")]
     pub ref_: String,
@@ -255,6 +328,7 @@ pub struct ExtendedPublicKeyRecord {
 
 /// A silent payments scan key expression label.
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
 pub struct SilentPaymentsScanRecord {
     #[serde(rename = "ref")]
     pub ref_: String,
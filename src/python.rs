@@ -0,0 +1,411 @@
+//! Python bindings, behind the `python` feature.
+//!
+//! Built as the `bip329` extension module with maturin, see `pyproject.toml`. Labels
+//! are exposed as `Label` objects, or as dicts in the BIP329 JSON shape, and errors are
+//! raised as the exceptions in [`exceptions`], or `OSError` for file errors.
+
+use std::path::PathBuf;
+
+use pyo3::{
+    exceptions::{PyIndexError, PyTypeError},
+    prelude::*,
+    types::{PyBool, PyBytes, PyDict, PyIterator, PyList},
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    encryption::{EncryptedLabels, PassphraseOptions},
+    error::{EncryptionError, ExportError, ParseError},
+    Label, LabelParseOptions, Labels,
+};
+
+mod exceptions {
+    use pyo3::{
+        create_exception,
+        exceptions::{PyException, PyValueError},
+    };
+
+    create_exception!(
+        bip329,
+        ParseError,
+        PyValueError,
+        "The labels are not valid BIP329."
+    );
+    create_exception!(
+        bip329,
+        ExportError,
+        PyException,
+        "The labels could not be exported."
+    );
+    create_exception!(
+        bip329,
+        EncryptionError,
+        PyException,
+        "The labels could not be encrypted or decrypted."
+    );
+    create_exception!(
+        bip329,
+        WrongPassphraseError,
+        EncryptionError,
+        "The passphrase can't decrypt the labels."
+    );
+}
+
+impl From<ParseError> for PyErr {
+    fn from(error: ParseError) -> Self {
+        match error {
+            ParseError::FileReadError(error) => error.into(),
+            error => exceptions::ParseError::new_err(error.to_string()),
+        }
+    }
+}
+
+impl From<ExportError> for PyErr {
+    fn from(error: ExportError) -> Self {
+        match error {
+            ExportError::FileWriteError(error) => error.into(),
+            error => exceptions::ExportError::new_err(error.to_string()),
+        }
+    }
+}
+
+impl From<EncryptionError> for PyErr {
+    fn from(error: EncryptionError) -> Self {
+        match error {
+            EncryptionError::WriteError(error) => error.into(),
+            EncryptionError::ParseError(error) => error.into(),
+            EncryptionError::WrongPassphrase => {
+                exceptions::WrongPassphraseError::new_err(error.to_string())
+            }
+            error => exceptions::EncryptionError::new_err(error.to_string()),
+        }
+    }
+}
+
+/// Options for parsing BIP329 JSONL
+#[pyclass(name = "LabelParseOptions", eq)]
+#[derive(Clone, Debug, PartialEq)]
+struct PyLabelParseOptions(LabelParseOptions);
+
+#[pymethods]
+impl PyLabelParseOptions {
+    #[new]
+    #[pyo3(signature = (ignore_unknown_types = false))]
+    fn new(ignore_unknown_types: bool) -> Self {
+        Self(LabelParseOptions::default().ignore_unknown_types(ignore_unknown_types))
+    }
+
+    /// Skip records whose `type` is not supported, instead of failing
+    #[getter]
+    fn ignore_unknown_types(&self) -> bool {
+        self.0.ignore_unknown_types
+    }
+
+    #[setter]
+    fn set_ignore_unknown_types(&mut self, ignore: bool) {
+        self.0 = self.0.ignore_unknown_types(ignore);
+    }
+
+    fn __repr__(&self) -> String {
+        let ignore = if self.0.ignore_unknown_types {
+            "True"
+        } else {
+            "False"
+        };
+        format!("LabelParseOptions(ignore_unknown_types={ignore})")
+    }
+}
+
+/// A single BIP329 label record
+#[pyclass(name = "Label", frozen, eq, hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct PyLabel(Label);
+
+#[pymethods]
+impl PyLabel {
+    /// Create a label from a dict in the BIP329 JSON shape
+    #[staticmethod]
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        label_from_dict(dict).map(Self)
+    }
+
+    /// The BIP329 `type`, e.g. `tx` or `output`
+    #[getter]
+    fn r#type(&self) -> &'static str {
+        self.0.ref_().type_tag()
+    }
+
+    /// The BIP329 `ref`, as written in JSONL
+    #[getter]
+    fn r#ref(&self) -> String {
+        self.0.ref_().to_string()
+    }
+
+    #[getter]
+    fn label(&self) -> Option<&str> {
+        self.0.label()
+    }
+
+    /// The descriptor a transaction was received with, `None` for other records
+    #[getter]
+    fn origin(&self) -> Option<&str> {
+        match &self.0 {
+            Label::Transaction(record) => record.origin.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether an output is spendable, which defaults to true, `None` for other records
+    #[getter]
+    fn spendable(&self) -> Option<bool> {
+        match &self.0 {
+            Label::Output(record) => Some(record.spendable()),
+            _ => None,
+        }
+    }
+
+    /// The label as a dict in the BIP329 JSON shape
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        label_to_dict(py, &self.0)
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        let json = serde_json::to_string(&self.0).map_err(ExportError::from)?;
+        Ok(format!("Label({json})"))
+    }
+}
+
+/// A list of BIP329 labels
+#[pyclass(name = "Labels", eq)]
+#[derive(Clone, Debug, PartialEq)]
+struct PyLabels(Labels);
+
+#[pymethods]
+impl PyLabels {
+    /// Create labels from an iterable of `Label`s or dicts
+    #[new]
+    #[pyo3(signature = (labels = None))]
+    fn new(labels: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(labels) = labels else {
+            return Ok(Self(Labels::default()));
+        };
+
+        let labels = labels
+            .try_iter()?
+            .map(|item| {
+                let item = item?;
+
+                if let Ok(label) = item.downcast::<PyLabel>() {
+                    return Ok(label.get().0.clone());
+                }
+
+                match item.downcast::<PyDict>() {
+                    Ok(dict) => label_from_dict(dict),
+                    Err(_) => Err(PyTypeError::new_err("Expected a Label or a dict")),
+                }
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        Ok(Self(Labels::new(labels)))
+    }
+
+    /// Parse labels from a JSONL string
+    #[staticmethod]
+    #[pyo3(signature = (jsonl, options = None))]
+    fn try_from_str(jsonl: &str, options: Option<PyLabelParseOptions>) -> PyResult<Self> {
+        let options = options.map(|options| options.0).unwrap_or_default();
+        Ok(Self(Labels::try_from_str_with_options(jsonl, options)?))
+    }
+
+    /// Parse labels from a JSONL file
+    #[staticmethod]
+    #[pyo3(signature = (path, options = None))]
+    fn try_from_file(path: PathBuf, options: Option<PyLabelParseOptions>) -> PyResult<Self> {
+        let options = options.map(|options| options.0).unwrap_or_default();
+        Ok(Self(Labels::try_from_file_with_options(path, options)?))
+    }
+
+    /// Export the labels to a JSONL string
+    fn export(&self) -> PyResult<String> {
+        Ok(self.0.export()?)
+    }
+
+    /// Export the labels to a JSONL file
+    fn export_to_file(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.0.export_to_file(path)?)
+    }
+
+    /// The labels as dicts in the BIP329 JSON shape
+    fn to_dicts<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.0
+            .iter()
+            .map(|label| label_to_dict(py, label))
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<PyLabel> {
+        let position = match index {
+            0.. => index.unsigned_abs(),
+            _ => self.0.len().wrapping_sub(index.unsigned_abs()),
+        };
+
+        self.0
+            .get(position)
+            .cloned()
+            .map(PyLabel)
+            .ok_or_else(|| PyIndexError::new_err("Labels index out of range"))
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        let labels: Vec<_> = self.0.iter().cloned().map(PyLabel).collect();
+        PyList::new(py, labels)?.try_iter()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Labels({} records)", self.0.len())
+    }
+}
+
+/// Labels encrypted with age
+#[pyclass(name = "EncryptedLabels", frozen, eq)]
+#[derive(Clone, Debug, PartialEq)]
+struct PyEncryptedLabels(EncryptedLabels);
+
+#[pymethods]
+impl PyEncryptedLabels {
+    /// Encrypt the labels with a passphrase
+    ///
+    /// `work_factor` is the base 2 logarithm of the scrypt cost, age picks one that
+    /// takes about a second when unset.
+    #[staticmethod]
+    #[pyo3(signature = (labels, passphrase, work_factor = None))]
+    fn encrypt(
+        py: Python<'_>,
+        labels: PyRef<'_, PyLabels>,
+        passphrase: &str,
+        work_factor: Option<u8>,
+    ) -> PyResult<Self> {
+        let options = match work_factor {
+            Some(work_factor) => PassphraseOptions::default().work_factor(work_factor),
+            None => PassphraseOptions::default(),
+        };
+
+        let labels = &labels.0;
+        let encrypted = py
+            .allow_threads(|| EncryptedLabels::encrypt_with_options(labels, passphrase, options))?;
+
+        Ok(Self(encrypted))
+    }
+
+    #[staticmethod]
+    fn from_hex(hex: &str) -> PyResult<Self> {
+        Ok(Self(EncryptedLabels::from_hex(hex)?))
+    }
+
+    #[staticmethod]
+    fn from_armor(armor: &str) -> PyResult<Self> {
+        Ok(Self(EncryptedLabels::from_armor(armor)?))
+    }
+
+    /// Binary, ASCII armored or hex encoded encrypted labels
+    #[staticmethod]
+    fn from_bytes(encoded: Vec<u8>) -> PyResult<Self> {
+        Ok(Self(EncryptedLabels::from_encoded(encoded)?))
+    }
+
+    /// Read binary, ASCII armored or hex encoded encrypted labels from a file
+    #[staticmethod]
+    fn read_from_file(path: PathBuf) -> PyResult<Self> {
+        Ok(Self(EncryptedLabels::read_from_file(path)?))
+    }
+
+    /// Decrypt the labels with a passphrase
+    fn decrypt(&self, py: Python<'_>, passphrase: &str) -> PyResult<PyLabels> {
+        let labels = py.allow_threads(|| self.0.decrypt(passphrase))?;
+        Ok(PyLabels(labels))
+    }
+
+    fn to_hex(&self) -> PyResult<String> {
+        Ok(self.0.to_hex()?)
+    }
+
+    fn to_armor(&self) -> PyResult<String> {
+        Ok(self.0.to_armor()?)
+    }
+
+    fn write_to_file(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.0.write_to_file(path)?)
+    }
+
+    fn __bytes__<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.clone().into_bytes())
+    }
+}
+
+/// The label in the BIP329 JSON shape, strings and booleans keyed by field name
+fn label_to_dict<'py>(py: Python<'py>, label: &Label) -> PyResult<Bound<'py, PyDict>> {
+    let value = serde_json::to_value(label).map_err(ExportError::from)?;
+    let dict = PyDict::new(py);
+
+    let Value::Object(fields) = value else {
+        unreachable!("labels serialize to JSON objects");
+    };
+
+    for (key, value) in fields {
+        match value {
+            Value::Null => dict.set_item(key, py.None())?,
+            Value::Bool(value) => dict.set_item(key, value)?,
+            Value::String(value) => dict.set_item(key, value)?,
+            value => dict.set_item(key, value.to_string())?,
+        }
+    }
+
+    Ok(dict)
+}
+
+fn label_from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Label> {
+    let mut fields = Map::new();
+
+    for (key, value) in dict.iter() {
+        let value = if value.is_none() {
+            Value::Null
+        } else if let Ok(value) = value.downcast::<PyBool>() {
+            Value::Bool(value.is_true())
+        } else {
+            Value::String(value.extract()?)
+        };
+
+        fields.insert(key.extract()?, value);
+    }
+
+    serde_json::from_value(Value::Object(fields))
+        .map_err(|error| exceptions::ParseError::new_err(error.to_string()))
+}
+
+#[pymodule]
+#[pyo3(name = "bip329")]
+fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = module.py();
+
+    module.add_class::<PyLabelParseOptions>()?;
+    module.add_class::<PyLabel>()?;
+    module.add_class::<PyLabels>()?;
+    module.add_class::<PyEncryptedLabels>()?;
+
+    module.add("ParseError", py.get_type::<exceptions::ParseError>())?;
+    module.add("ExportError", py.get_type::<exceptions::ExportError>())?;
+    module.add(
+        "EncryptionError",
+        py.get_type::<exceptions::EncryptionError>(),
+    )?;
+    module.add(
+        "WrongPassphraseError",
+        py.get_type::<exceptions::WrongPassphraseError>(),
+    )?;
+
+    Ok(())
+}
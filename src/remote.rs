@@ -0,0 +1,86 @@
+//! Module for self-hosted label sync, with a server storing the encrypted labels of
+//! each wallet (`server` feature) and a client for it (`client` feature).
+//!
+//! The server only ever sees [`EncryptedLabels`](crate::encryption::EncryptedLabels),
+//! stored as binary age files. Each upload of a wallet's labels creates a new version,
+//! and older versions are kept. Uploads use optimistic concurrency: a client states the
+//! version its edits are based on, and the upload is rejected if another device has
+//! uploaded a newer version since, so no device silently overwrites another's labels.
+//!
+//! The HTTP API, with versions as strong `ETag`s like `"3"`:
+//!
+//! | Request | Response |
+//! |---------|----------|
+//! | `GET /wallets/{wallet}` | The latest version, `404` if the wallet has no labels |
+//! | `GET /wallets/{wallet}/versions` | The stored versions, as a JSON array |
+//! | `GET /wallets/{wallet}/versions/{version}` | A stored version |
+//! | `PUT /wallets/{wallet}` with `If-Match: "{version}"` | Upload a new version based on `version` |
+//! | `PUT /wallets/{wallet}` with `If-Match: *` | Upload a new version based on any existing one |
+//! | `PUT /wallets/{wallet}` with `If-None-Match: *` | Upload the first version |
+//!
+//! Successful uploads respond with `201` and the new version's `ETag`. Uploads without
+//! either header are rejected with `428`, and uploads whose precondition fails with
+//! `412` and the latest version's `ETag`, if there is one. A server started with an
+//! access token requires it as an `Authorization: Bearer` header.
+//!
+//! ```rust
+#![cfg_attr(
+    not(all(feature = "server", feature = "client")),
+    doc = "# // This example requires the `server` and `client` features"
+)]
+//! # #[cfg(all(feature = "server", feature = "client"))]
+//! # {
+//! use bip329::{
+//!     encryption::{EncryptedLabels, PassphraseOptions},
+//!     remote::{client::LabelClient, server::LabelServer},
+//!     Labels,
+//! };
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! let server = LabelServer::bind("127.0.0.1:0", dir.path()).unwrap().spawn();
+//! let client = LabelClient::new(server.url());
+//!
+//! let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+//! let options = PassphraseOptions::default().work_factor(10);
+//! let encrypted = EncryptedLabels::encrypt_with_options(&labels, "passphrase", options).unwrap();
+//!
+//! let version = client.upload("my-wallet", &encrypted, None).unwrap();
+//! let remote = client.download("my-wallet").unwrap().unwrap();
+//! assert_eq!(remote.version, version);
+//! assert_eq!(remote.labels.decrypt("passphrase").unwrap(), labels);
+//!
+//! server.shutdown();
+//! # }
+//! ```
+
+#[cfg(feature = "client")]
+pub mod client;
+
+#[cfg(feature = "server")]
+pub mod server;
+
+/// The largest encrypted labels file the server accepts, in bytes
+pub const MAX_LABELS_SIZE: usize = 64 * 1024 * 1024;
+
+/// Whether the wallet id can be used with the server: 1 to 64 ASCII letters, digits,
+/// `-` or `_`.
+pub fn is_valid_wallet_id(wallet: &str) -> bool {
+    (1..=64).contains(&wallet.len())
+        && wallet
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+}
+
+/// The `ETag` header value for a version
+fn etag(version: u64) -> String {
+    format!("\"{version}\"")
+}
+
+/// The version in an `ETag` or `If-Match` header value
+fn parse_etag(etag: &str) -> Option<u64> {
+    etag.trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')?
+        .parse()
+        .ok()
+}
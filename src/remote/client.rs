@@ -0,0 +1,184 @@
+//! The label sync client, for a server started with [`LabelServer`](super::server::LabelServer)
+//! or the `bip329-server` binary.
+
+use std::time::Duration;
+
+use ureq::{http::Response, Agent, Body};
+
+use super::{etag, is_valid_wallet_id, parse_etag, MAX_LABELS_SIZE};
+use crate::{encryption::EncryptedLabels, error::ClientError};
+
+/// A version of a wallet's labels downloaded from the server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteLabels {
+    /// The version, to pass to [`LabelClient::upload`] for uploading edits of these labels
+    pub version: u64,
+    /// The encrypted labels
+    pub labels: EncryptedLabels,
+}
+
+/// A client for a label sync server
+#[derive(Clone, Debug)]
+pub struct LabelClient {
+    agent: Agent,
+    url: String,
+    token: Option<String>,
+}
+
+impl LabelClient {
+    /// Create a client for the server at the base URL, like `http://127.0.0.1:8329`.
+    pub fn new(url: impl Into<String>) -> Self {
+        let agent = Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(Duration::from_secs(60)))
+            .build()
+            .into();
+
+        Self {
+            agent,
+            url: url.into().trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Send the access token the server was started with.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Download the latest version of the wallet's labels, `None` if it has none.
+    pub fn download(&self, wallet: &str) -> Result<Option<RemoteLabels>, ClientError> {
+        self.get_labels(&self.wallet_url(wallet)?)
+    }
+
+    /// Download a stored version of the wallet's labels, `None` if it does not exist.
+    pub fn download_version(
+        &self,
+        wallet: &str,
+        version: u64,
+    ) -> Result<Option<RemoteLabels>, ClientError> {
+        let url = format!("{}/versions/{version}", self.wallet_url(wallet)?);
+        self.get_labels(&url)
+    }
+
+    /// The stored versions of the wallet's labels, oldest first.
+    pub fn versions(&self, wallet: &str) -> Result<Vec<u64>, ClientError> {
+        let url = format!("{}/versions", self.wallet_url(wallet)?);
+        let mut response = self.authorize(self.agent.get(&url)).call()?;
+
+        match response.status().as_u16() {
+            200 => {
+                let json = response.body_mut().read_to_string()?;
+                serde_json::from_str(&json).map_err(|error| ClientError::UnexpectedResponse {
+                    status: 200,
+                    message: error.to_string(),
+                })
+            }
+            404 => Ok(Vec::new()),
+            _ => Err(unexpected(response)),
+        }
+    }
+
+    /// Upload a new version of the wallet's labels, and return the new version.
+    ///
+    /// `base` is the version the labels were edited from, or `None` if the wallet has
+    /// no labels on the server yet. Fails with [`ClientError::VersionConflict`] when
+    /// the latest version on the server is a different one, then download the latest
+    /// version, merge the edits into it and upload again.
+    pub fn upload(
+        &self,
+        wallet: &str,
+        labels: &EncryptedLabels,
+        base: Option<u64>,
+    ) -> Result<u64, ClientError> {
+        let url = self.wallet_url(wallet)?;
+
+        let request = self
+            .authorize(self.agent.put(&url))
+            .header("Content-Type", "application/octet-stream");
+
+        let request = match base {
+            Some(version) => request.header("If-Match", etag(version)),
+            None => request.header("If-None-Match", "*"),
+        };
+
+        let response = request.send(labels.as_bytes())?;
+
+        match response.status().as_u16() {
+            201 => version(&response).ok_or_else(|| missing_version(&response)),
+            412 => Err(ClientError::VersionConflict {
+                current: version(&response),
+            }),
+            _ => Err(unexpected(response)),
+        }
+    }
+
+    fn get_labels(&self, url: &str) -> Result<Option<RemoteLabels>, ClientError> {
+        let mut response = self.authorize(self.agent.get(url)).call()?;
+
+        match response.status().as_u16() {
+            200 => {
+                let version = version(&response).ok_or_else(|| missing_version(&response))?;
+                let encrypted = response
+                    .body_mut()
+                    .with_config()
+                    .limit(MAX_LABELS_SIZE as u64)
+                    .read_to_vec()?;
+
+                Ok(Some(RemoteLabels {
+                    version,
+                    labels: EncryptedLabels::from_encoded(encrypted)?,
+                }))
+            }
+            404 => Ok(None),
+            _ => Err(unexpected(response)),
+        }
+    }
+
+    fn wallet_url(&self, wallet: &str) -> Result<String, ClientError> {
+        if !is_valid_wallet_id(wallet) {
+            return Err(ClientError::InvalidWalletId(wallet.to_string()));
+        }
+
+        Ok(format!("{}/wallets/{wallet}", self.url))
+    }
+
+    fn authorize<B>(&self, request: ureq::RequestBuilder<B>) -> ureq::RequestBuilder<B> {
+        match &self.token {
+            Some(token) => request.header("Authorization", format!("Bearer {token}")),
+            None => request,
+        }
+    }
+}
+
+/// The version in the response's `ETag`
+fn version(response: &Response<Body>) -> Option<u64> {
+    let etag = response.headers().get("ETag")?.to_str().ok()?;
+    parse_etag(etag)
+}
+
+fn missing_version(response: &Response<Body>) -> ClientError {
+    ClientError::UnexpectedResponse {
+        status: response.status().as_u16(),
+        message: "Missing or invalid ETag version".to_string(),
+    }
+}
+
+fn unexpected(mut response: Response<Body>) -> ClientError {
+    let status = response.status().as_u16();
+
+    if status == 401 {
+        return ClientError::Unauthorized;
+    }
+
+    // the server's error messages are short plain text
+    let message = response
+        .body_mut()
+        .with_config()
+        .limit(4096)
+        .read_to_string()
+        .unwrap_or_default();
+
+    ClientError::UnexpectedResponse { status, message }
+}
@@ -0,0 +1,323 @@
+//! The label sync server, see the [`remote`](crate::remote) module for its HTTP API.
+//!
+//! Each wallet's versions are stored as `{data dir}/{wallet}/{version}.age`. Requests
+//! are handled one at a time, so a data directory must only be served by one server.
+
+use std::{
+    io::{self, Cursor, Read as _},
+    net::{SocketAddr, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::JoinHandle,
+};
+
+use tiny_http::{Header, Method, Request, Response};
+
+use super::{etag, is_valid_wallet_id, parse_etag, MAX_LABELS_SIZE};
+use crate::{encryption::EncryptedLabels, error::ServerError};
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// A label sync server, storing encrypted labels in a data directory
+pub struct LabelServer {
+    http: Arc<tiny_http::Server>,
+    addr: SocketAddr,
+    dir: PathBuf,
+    token: Option<String>,
+}
+
+/// A server running on a background thread, see [`LabelServer::spawn`]
+pub struct ServerHandle {
+    http: Arc<tiny_http::Server>,
+    addr: SocketAddr,
+    thread: JoinHandle<()>,
+}
+
+/// The version an upload must be based on, from its `If-Match` or `If-None-Match`
+enum Precondition {
+    /// `If-Match: "{version}"`
+    Version(u64),
+    /// `If-Match: *`, any existing version
+    Exists,
+    /// `If-None-Match: *`, the first upload
+    Missing,
+}
+
+/// The request routes of the HTTP API
+enum Route {
+    Latest(String),
+    Versions(String),
+    Version(String, u64),
+    Upload(String),
+}
+
+impl LabelServer {
+    /// Listen on the address, storing labels in the data directory.
+    ///
+    /// Bind to port `0` to listen on any free port, see [`LabelServer::local_addr`].
+    pub fn bind(addr: impl ToSocketAddrs, dir: impl AsRef<Path>) -> Result<Self, ServerError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        let http = tiny_http::Server::http(addr).map_err(ServerError::BindError)?;
+        let addr = http
+            .server_addr()
+            .to_ip()
+            .expect("tiny_http::Server::http listens on TCP");
+
+        Ok(Self {
+            http: Arc::new(http),
+            addr,
+            dir,
+            token: None,
+        })
+    }
+
+    /// Require the access token in an `Authorization: Bearer` header on every request.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Handle requests until the server is shut down.
+    pub fn run(&self) {
+        for request in self.http.incoming_requests() {
+            self.handle(request);
+        }
+    }
+
+    /// Handle requests on a background thread.
+    pub fn spawn(self) -> ServerHandle {
+        let http = self.http.clone();
+        let addr = self.addr;
+        let thread = std::thread::spawn(move || self.run());
+
+        ServerHandle { http, addr, thread }
+    }
+
+    fn handle(&self, mut request: Request) {
+        let response = self.respond(&mut request).unwrap_or_else(|error| {
+            text(500, &format!("Unable to access the label store: {error}"))
+        });
+
+        // the client may have closed the connection already, nothing is left to do then
+        let _ = request.respond(response);
+    }
+
+    fn respond(&self, request: &mut Request) -> io::Result<HttpResponse> {
+        if !self.is_authorized(request) {
+            return Ok(text(401, "Missing or invalid access token"));
+        }
+
+        let route = match route(request.method(), request.url()) {
+            Ok(route) => route,
+            Err(response) => return Ok(response),
+        };
+
+        match route {
+            Route::Latest(wallet) => match self.versions(&wallet)?.last() {
+                Some(&version) => self.download(&wallet, version),
+                None => Ok(text(404, "No labels for this wallet")),
+            },
+            Route::Versions(wallet) => match self.versions(&wallet)? {
+                versions if versions.is_empty() => Ok(text(404, "No labels for this wallet")),
+                versions => {
+                    let json = serde_json::to_vec(&versions)?;
+                    Ok(Response::from_data(json).with_header(content_type("application/json")))
+                }
+            },
+            Route::Version(wallet, version) => self.download(&wallet, version),
+            Route::Upload(wallet) => self.upload(&wallet, request),
+        }
+    }
+
+    fn is_authorized(&self, request: &Request) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+
+        header(request, "Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+    }
+
+    fn download(&self, wallet: &str, version: u64) -> io::Result<HttpResponse> {
+        match std::fs::read(self.path(wallet, version)) {
+            Ok(encrypted) => Ok(Response::from_data(encrypted)
+                .with_header(content_type("application/octet-stream"))
+                .with_header(etag_header(version))),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                Ok(text(404, "No such version of the labels"))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn upload(&self, wallet: &str, request: &mut Request) -> io::Result<HttpResponse> {
+        let precondition = match (
+            header(request, "If-Match"),
+            header(request, "If-None-Match"),
+        ) {
+            (Some("*"), _) => Precondition::Exists,
+            (Some(if_match), _) => match parse_etag(if_match) {
+                Some(version) => Precondition::Version(version),
+                None => return Ok(text(400, "Invalid If-Match version")),
+            },
+            (None, Some("*")) => Precondition::Missing,
+            _ => {
+                let message = "Uploads require If-Match, or If-None-Match: * for the first one";
+                return Ok(text(428, message));
+            }
+        };
+
+        if request.body_length().unwrap_or_default() > MAX_LABELS_SIZE {
+            return Ok(text(413, "The labels are too large"));
+        }
+
+        let mut body = Vec::new();
+        let limit = MAX_LABELS_SIZE as u64 + 1;
+        request.as_reader().take(limit).read_to_end(&mut body)?;
+
+        if body.len() > MAX_LABELS_SIZE {
+            return Ok(text(413, "The labels are too large"));
+        }
+
+        let encrypted = match EncryptedLabels::from_encoded(body) {
+            Ok(encrypted) if encrypted.is_age_file() => encrypted,
+            _ => return Ok(text(400, "The body is not age encrypted labels")),
+        };
+
+        let latest = self.versions(wallet)?.last().copied();
+        let matches = match precondition {
+            Precondition::Version(version) => latest == Some(version),
+            Precondition::Exists => latest.is_some(),
+            Precondition::Missing => latest.is_none(),
+        };
+
+        if !matches {
+            let response = text(412, "The labels were changed since the base version");
+
+            return Ok(match latest {
+                Some(latest) => response.with_header(etag_header(latest)),
+                None => response,
+            });
+        }
+
+        let version = latest.map_or(1, |latest| latest + 1);
+        let path = self.path(wallet, version);
+        let temp_path = path.with_extension("age.tmp");
+
+        std::fs::create_dir_all(self.dir.join(wallet))?;
+        std::fs::write(&temp_path, encrypted.as_bytes())?;
+        std::fs::rename(&temp_path, &path)?;
+
+        Ok(Response::from_data(Vec::new())
+            .with_status_code(201)
+            .with_header(etag_header(version)))
+    }
+
+    /// The stored versions of the wallet's labels, oldest first
+    fn versions(&self, wallet: &str) -> io::Result<Vec<u64>> {
+        let entries = match std::fs::read_dir(self.dir.join(wallet)) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+
+        let mut versions = Vec::new();
+        for entry in entries {
+            let name = entry?.file_name();
+            let version = name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".age"))
+                .and_then(|version| version.parse::<u64>().ok());
+
+            versions.extend(version);
+        }
+
+        versions.sort_unstable();
+        Ok(versions)
+    }
+
+    fn path(&self, wallet: &str, version: u64) -> PathBuf {
+        self.dir.join(wallet).join(format!("{version}.age"))
+    }
+}
+
+impl ServerHandle {
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The base URL of the server, for [`LabelClient::new`](super::client::LabelClient::new).
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Stop handling requests, after the request being handled, and wait for the
+    /// server thread to finish.
+    pub fn shutdown(self) {
+        self.http.unblock();
+        let _ = self.thread.join();
+    }
+}
+
+fn route(method: &Method, url: &str) -> Result<Route, HttpResponse> {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+    let ["wallets", wallet, rest @ ..] = segments.as_slice() else {
+        return Err(text(404, "Not found"));
+    };
+
+    if !is_valid_wallet_id(wallet) {
+        return Err(text(400, "Invalid wallet id"));
+    }
+
+    let wallet = wallet.to_string();
+
+    match (method, rest) {
+        (Method::Get, []) => Ok(Route::Latest(wallet)),
+        (Method::Put, []) => Ok(Route::Upload(wallet)),
+        (Method::Get, ["versions"]) => Ok(Route::Versions(wallet)),
+        (Method::Get, ["versions", version]) => match version.parse() {
+            Ok(version) => Ok(Route::Version(wallet, version)),
+            Err(_) => Err(text(404, "No such version of the labels")),
+        },
+        (_, [] | ["versions"] | ["versions", _]) => Err(text(405, "Method not allowed")),
+        _ => Err(text(404, "Not found")),
+    }
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+fn text(status: u16, message: &str) -> HttpResponse {
+    Response::from_string(message)
+        .with_status_code(status)
+        .with_header(content_type("text/plain; charset=utf-8"))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("valid header")
+}
+
+fn etag_header(version: u64) -> Header {
+    Header::from_bytes("ETag", etag(version)).expect("valid header")
+}
+
+/// Compare without returning early, so response times don't leak the token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
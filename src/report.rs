@@ -0,0 +1,507 @@
+//! Module for rendering labels as human readable reports.
+//!
+//! A [`LabelReport`] groups transaction, input and output labels by transaction, and
+//! lists the remaining labels by type. Reports can be rendered as Markdown or as a
+//! self-contained HTML page. [`Labels::group_by_transaction`] returns the same
+//! grouping as indices into the labels, for other views of them.
+//!
+//! ### Example:
+//! ```rust
+//! use bip329::Labels;
+//!
+//! let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+//! let markdown = labels.report().to_markdown();
+//! ```
+
+use std::{collections::HashMap, fmt::Write as _};
+
+use bitcoin::Txid;
+
+use crate::{
+    AddressRecord, ExtendedPublicKeyRecord, InputRecord, Label, Labels, OutputRecord,
+    PublicKeyRecord, SilentPaymentsScanRecord, TransactionRecord,
+};
+
+const NO_LABEL: &str = "(no label)";
+
+/// A section title with the `ref` and label of each record in it
+type Section<'a> = (&'static str, Vec<(String, Option<&'a str>)>);
+
+/// A label report, grouped by transaction.
+///
+/// Created with [`Labels::report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabelReport<'a> {
+    transactions: Vec<TransactionGroup<'a>>,
+    addresses: Vec<&'a AddressRecord>,
+    public_keys: Vec<&'a PublicKeyRecord>,
+    xpubs: Vec<&'a ExtendedPublicKeyRecord>,
+    silent_payments_scan_keys: Vec<&'a SilentPaymentsScanRecord>,
+}
+
+/// A transaction together with its labelled inputs and outputs
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TransactionGroup<'a> {
+    txid: Txid,
+    record: Option<&'a TransactionRecord>,
+    inputs: Vec<&'a InputRecord>,
+    outputs: Vec<&'a OutputRecord>,
+}
+
+/// Labels grouped by transaction, as indices into the labels.
+///
+/// Created with [`Labels::group_by_transaction`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LabelGroups {
+    /// The transactions, in the order they first appear
+    pub transactions: Vec<TransactionLabels>,
+    /// The labels that are not grouped under a transaction, in order
+    pub others: Vec<usize>,
+}
+
+/// The labels of a transaction and its inputs and outputs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionLabels {
+    pub txid: Txid,
+    /// The transaction record, `None` if only its inputs or outputs are labelled
+    pub record: Option<usize>,
+    /// The input records, ordered by index
+    pub inputs: Vec<usize>,
+    /// The output records, ordered by index
+    pub outputs: Vec<usize>,
+}
+
+impl Labels {
+    /// Create a report of the labels, grouped by transaction.
+    pub fn report(&self) -> LabelReport<'_> {
+        LabelReport::new(self)
+    }
+
+    /// Group the transaction, input and output labels by transaction, like
+    /// [`Labels::report`].
+    pub fn group_by_transaction(&self) -> LabelGroups {
+        let mut groups = LabelGroups::default();
+        let mut positions = HashMap::new();
+
+        for (index, label) in self.iter().enumerate() {
+            let txid = match label {
+                Label::Transaction(record) => record.ref_,
+                Label::Input(record) => record.ref_.txid,
+                Label::Output(record) => record.ref_.txid,
+                _ => {
+                    groups.others.push(index);
+                    continue;
+                }
+            };
+
+            let transactions = &mut groups.transactions;
+            let position = *positions.entry(txid).or_insert_with(|| {
+                transactions.push(TransactionLabels {
+                    txid,
+                    record: None,
+                    inputs: Vec::new(),
+                    outputs: Vec::new(),
+                });
+                transactions.len() - 1
+            });
+
+            let transaction = &mut transactions[position];
+            match label {
+                Label::Input(_) => transaction.inputs.push(index),
+                Label::Output(_) => transaction.outputs.push(index),
+                _ => transaction.record = Some(index),
+            }
+        }
+
+        for transaction in &mut groups.transactions {
+            transaction.inputs.sort_by_key(|&index| vout(&self[index]));
+            transaction.outputs.sort_by_key(|&index| vout(&self[index]));
+        }
+
+        groups
+    }
+}
+
+impl<'a> LabelReport<'a> {
+    /// Create a new report from the given labels.
+    ///
+    /// Transactions keep the order they first appear in, inputs and outputs are
+    /// ordered by their index.
+    pub fn new(labels: &'a Labels) -> Self {
+        let groups = labels.group_by_transaction();
+
+        let transactions = groups
+            .transactions
+            .iter()
+            .map(|transaction| TransactionGroup {
+                txid: transaction.txid,
+                record: transaction.record.and_then(|index| match &labels[index] {
+                    Label::Transaction(record) => Some(record),
+                    _ => None,
+                }),
+                inputs: records(labels, &transaction.inputs, |label| match label {
+                    Label::Input(record) => Some(record),
+                    _ => None,
+                }),
+                outputs: records(labels, &transaction.outputs, |label| match label {
+                    Label::Output(record) => Some(record),
+                    _ => None,
+                }),
+            })
+            .collect();
+
+        let mut report = Self {
+            transactions,
+            addresses: Vec::new(),
+            public_keys: Vec::new(),
+            xpubs: Vec::new(),
+            silent_payments_scan_keys: Vec::new(),
+        };
+
+        for &index in &groups.others {
+            match &labels[index] {
+                Label::Address(record) => report.addresses.push(record),
+                Label::PublicKey(record) => report.public_keys.push(record),
+                Label::ExtendedPublicKey(record) => report.xpubs.push(record),
+                Label::SilentPaymentsScan(record) => report.silent_payments_scan_keys.push(record),
+                Label::Transaction(_) | Label::Input(_) | Label::Output(_) => {
+                    unreachable!("grouped by transaction")
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Render the report as Markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Label Report\n");
+
+        for transaction in &self.transactions {
+            let _ = write!(out, "\n## Transaction `{}`\n\n", transaction.txid);
+
+            let label = transaction
+                .record
+                .and_then(|record| record.label.as_deref());
+            let _ = writeln!(out, "- **Label:** {}", markdown_label(label));
+
+            if let Some(origin) = transaction.record.and_then(|r| r.origin.as_deref()) {
+                let _ = writeln!(out, "- **Origin:** `{}`", markdown_code(origin));
+            }
+
+            if !transaction.inputs.is_empty() {
+                out.push_str("\n### Inputs\n\n");
+                for input in &transaction.inputs {
+                    let label = markdown_label(input.label.as_deref());
+                    let _ = writeln!(out, "- `{}`: {label}", input.ref_.vout);
+                }
+            }
+
+            if !transaction.outputs.is_empty() {
+                out.push_str("\n### Outputs\n\n");
+                for output in &transaction.outputs {
+                    let label = markdown_label(output.label.as_deref());
+                    let spendable = spendable_status(output);
+                    let _ = writeln!(out, "- `{}`: {label} _({spendable})_", output.ref_.vout);
+                }
+            }
+        }
+
+        for (title, records) in self.other_sections() {
+            if records.is_empty() {
+                continue;
+            }
+
+            let _ = write!(out, "\n## {title}\n\n");
+            for (ref_, label) in records {
+                let label = markdown_label(label);
+                let _ = writeln!(out, "- `{}`: {label}", markdown_code(&ref_));
+            }
+        }
+
+        out
+    }
+
+    /// Render the report as a self-contained HTML page.
+    pub fn to_html(&self) -> String {
+        let mut out = String::from(concat!(
+            "<!DOCTYPE html>\n",
+            "<html lang=\"en\">\n",
+            "<head>\n",
+            "<meta charset=\"utf-8\">\n",
+            "<title>Label Report</title>\n",
+            "<style>\n",
+            "body { font-family: sans-serif; margin: 2em auto; max-width: 60em; }\n",
+            "code { word-break: break-all; }\n",
+            "section { border-top: 1px solid #ccc; }\n",
+            ".empty { color: #888; font-style: italic; }\n",
+            ".unspendable { color: #b00; }\n",
+            "</style>\n",
+            "</head>\n",
+            "<body>\n",
+            "<h1>Label Report</h1>\n",
+        ));
+
+        for transaction in &self.transactions {
+            out.push_str("<section>\n");
+            let _ = writeln!(
+                out,
+                "<h2>Transaction <code>{}</code></h2>",
+                transaction.txid
+            );
+
+            let label = transaction
+                .record
+                .and_then(|record| record.label.as_deref());
+            let _ = writeln!(out, "<p><strong>Label:</strong> {}</p>", html_label(label));
+
+            if let Some(origin) = transaction.record.and_then(|r| r.origin.as_deref()) {
+                let origin = html_escape(origin);
+                let _ = writeln!(out, "<p><strong>Origin:</strong> <code>{origin}</code></p>");
+            }
+
+            if !transaction.inputs.is_empty() {
+                out.push_str("<h3>Inputs</h3>\n<ul>\n");
+                for input in &transaction.inputs {
+                    let label = html_label(input.label.as_deref());
+                    let _ = writeln!(out, "<li><code>{}</code>: {label}</li>", input.ref_.vout);
+                }
+                out.push_str("</ul>\n");
+            }
+
+            if !transaction.outputs.is_empty() {
+                out.push_str("<h3>Outputs</h3>\n<ul>\n");
+                for output in &transaction.outputs {
+                    let label = html_label(output.label.as_deref());
+                    let class = if output.spendable() {
+                        "spendable"
+                    } else {
+                        "unspendable"
+                    };
+                    let _ = writeln!(
+                        out,
+                        "<li><code>{}</code>: {label} <span class=\"{class}\">({})</span></li>",
+                        output.ref_.vout,
+                        spendable_status(output)
+                    );
+                }
+                out.push_str("</ul>\n");
+            }
+
+            out.push_str("</section>\n");
+        }
+
+        for (title, records) in self.other_sections() {
+            if records.is_empty() {
+                continue;
+            }
+
+            let _ = writeln!(out, "<section>\n<h2>{title}</h2>\n<ul>");
+            for (ref_, label) in records {
+                let ref_ = html_escape(&ref_);
+                let label = html_label(label);
+                let _ = writeln!(out, "<li><code>{ref_}</code>: {label}</li>");
+            }
+            out.push_str("</ul>\n</section>\n");
+        }
+
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    /// Sections for labels that are not grouped under a transaction
+    fn other_sections(&self) -> [Section<'a>; 4] {
+        fn entries<'a, T>(
+            records: &[&'a T],
+            entry: impl Fn(&'a T) -> (String, Option<&'a str>),
+        ) -> Vec<(String, Option<&'a str>)> {
+            records.iter().map(|record| entry(record)).collect()
+        }
+
+        [
+            (
+                "Addresses",
+                entries(&self.addresses, |r| {
+                    let address = r.ref_.clone().assume_checked();
+                    (address.to_string(), r.label.as_deref())
+                }),
+            ),
+            (
+                "Public Keys",
+                entries(&self.public_keys, |r| (r.ref_.clone(), r.label.as_deref())),
+            ),
+            (
+                "Extended Public Keys",
+                entries(&self.xpubs, |r| (r.ref_.clone(), r.label.as_deref())),
+            ),
+            (
+                "Silent Payments Scan Keys",
+                entries(&self.silent_payments_scan_keys, |r| {
+                    (r.ref_.clone(), r.label.as_deref())
+                }),
+            ),
+        ]
+    }
+}
+
+/// The records of the labels at the indices
+fn records<'a, T>(
+    labels: &'a Labels,
+    indices: &[usize],
+    record: impl Fn(&'a Label) -> Option<&'a T>,
+) -> Vec<&'a T> {
+    indices
+        .iter()
+        .filter_map(|&index| record(&labels[index]))
+        .collect()
+}
+
+/// The index of an input or output
+fn vout(label: &Label) -> u32 {
+    match label {
+        Label::Input(record) => record.ref_.vout,
+        Label::Output(record) => record.ref_.vout,
+        _ => 0,
+    }
+}
+
+fn spendable_status(output: &OutputRecord) -> &'static str {
+    if output.spendable() {
+        "spendable"
+    } else {
+        "not spendable"
+    }
+}
+
+fn markdown_label(label: Option<&str>) -> String {
+    match label.filter(|label| !label.is_empty()) {
+        Some(label) => markdown_escape(label),
+        None => format!("_{NO_LABEL}_"),
+    }
+}
+
+/// Escape characters that Markdown would otherwise interpret, and flatten newlines
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Inline code spans can't contain backticks or newlines
+fn markdown_code(text: &str) -> String {
+    text.replace(['`', '\r', '\n'], " ")
+}
+
+fn html_label(label: Option<&str>) -> String {
+    match label.filter(|label| !label.is_empty()) {
+        Some(label) => html_escape(label),
+        None => format!("<span class=\"empty\">{NO_LABEL}</span>"),
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSONL: &str = r#"{"type": "output", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:1", "label": "Change", "spendable": false}
+{"type": "tx", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd", "label": "Rent <March> & *fees*", "origin": "wpkh([d34db33f/84'/0'/0'])"}
+{"type": "output", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:0", "label": "Payment"}
+{"type": "input", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:0"}
+{"type": "addr", "ref": "bc1q34aq5drpuwy3wgl9lhup9892qp6svr8ldzyy7c", "label": "Address"}"#;
+
+    #[test]
+    fn markdown_report_groups_by_transaction() {
+        let labels = Labels::try_from_str(JSONL).unwrap();
+        let markdown = labels.report().to_markdown();
+
+        let expected = "# Label Report
+
+## Transaction `f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd`
+
+- **Label:** Rent \\<March\\> & \\*fees\\*
+- **Origin:** `wpkh([d34db33f/84'/0'/0'])`
+
+### Inputs
+
+- `0`: _(no label)_
+
+### Outputs
+
+- `0`: Payment _(spendable)_
+- `1`: Change _(not spendable)_
+
+## Addresses
+
+- `bc1q34aq5drpuwy3wgl9lhup9892qp6svr8ldzyy7c`: Address
+";
+
+        pretty_assertions::assert_eq!(markdown, expected);
+    }
+
+    #[test]
+    fn labels_are_grouped_by_transaction() {
+        let labels = Labels::try_from_str(JSONL).unwrap();
+        let groups = labels.group_by_transaction();
+
+        assert_eq!(groups.transactions.len(), 1);
+        let transaction = &groups.transactions[0];
+        assert_eq!(
+            transaction.txid,
+            labels[1].ref_().to_string().parse().unwrap()
+        );
+        assert_eq!(transaction.record, Some(1));
+        assert_eq!(transaction.inputs, vec![3]);
+        assert_eq!(transaction.outputs, vec![2, 0]);
+        assert_eq!(groups.others, vec![4]);
+    }
+
+    #[test]
+    fn html_report_escapes_labels() {
+        let labels = Labels::try_from_str(JSONL).unwrap();
+        let html = labels.report().to_html();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Rent &lt;March&gt; &amp; *fees*"));
+        assert!(html.contains("<span class=\"unspendable\">(not spendable)</span>"));
+        assert!(html.contains("<h2>Addresses</h2>"));
+        assert!(!html.contains("Public Keys"));
+    }
+
+    #[test]
+    fn report_includes_every_label() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let markdown = labels.report().to_markdown();
+
+        for label in labels.iter() {
+            if let Some(text) = label.label() {
+                assert!(markdown.contains(&markdown_escape(text)));
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Module for storing labels in a key-value backend.
+//!
+//! The [`LabelRepository`] trait stores one [`Label`] per [`LabelRef`], so wallet
+//! code can switch between backends without changing how it reads or writes labels.
+//!
+//! - [`MemoryLabelRepository`] keeps labels in memory.
+//! - [`RedbLabelRepository`] stores labels in a [redb](https://docs.rs/redb) database
+//!   (requires the `redb` feature).
+
+use std::{collections::BTreeMap, convert::Infallible};
+
+use crate::{Label, LabelRef, Labels};
+
+#[cfg(feature = "redb")]
+mod redb;
+
+#[cfg(feature = "redb")]
+pub use self::redb::RedbLabelRepository;
+
+/// A store of labels keyed by their [`LabelRef`].
+pub trait LabelRepository {
+    /// The error returned by the backend.
+    type Error;
+
+    /// Get the label stored for the given reference.
+    fn get(&self, ref_: &LabelRef) -> Result<Option<Label>, Self::Error>;
+
+    /// Insert or replace a label, returning the label it replaced.
+    fn put(&mut self, label: Label) -> Result<Option<Label>, Self::Error>;
+
+    /// Delete the label for the given reference, returning the removed label.
+    fn delete(&mut self, ref_: &LabelRef) -> Result<Option<Label>, Self::Error>;
+
+    /// Get an iterator over all stored labels, the order is backend specific.
+    fn iter(&self) -> Result<impl Iterator<Item = Label> + '_, Self::Error>;
+
+    /// Insert or replace every label in the given Labels struct.
+    ///
+    /// When the same reference appears more than once, the last label wins.
+    fn import(&mut self, labels: Labels) -> Result<(), Self::Error> {
+        labels
+            .into_vec()
+            .into_iter()
+            .try_for_each(|label| self.put(label).map(|_| ()))
+    }
+
+    /// Collect all stored labels into a Labels struct.
+    fn export(&self) -> Result<Labels, Self::Error> {
+        Ok(Labels::new(self.iter()?.collect()))
+    }
+}
+
+/// A [`LabelRepository`] that keeps labels in memory, ordered by [`LabelRef`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryLabelRepository(BTreeMap<LabelRef, Label>);
+
+impl MemoryLabelRepository {
+    /// Create a new empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of stored labels.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no labels are stored.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl LabelRepository for MemoryLabelRepository {
+    type Error = Infallible;
+
+    fn get(&self, ref_: &LabelRef) -> Result<Option<Label>, Self::Error> {
+        Ok(self.0.get(ref_).cloned())
+    }
+
+    fn put(&mut self, label: Label) -> Result<Option<Label>, Self::Error> {
+        Ok(self.0.insert(label.ref_(), label))
+    }
+
+    fn delete(&mut self, ref_: &LabelRef) -> Result<Option<Label>, Self::Error> {
+        Ok(self.0.remove(ref_))
+    }
+
+    fn iter(&self) -> Result<impl Iterator<Item = Label> + '_, Self::Error> {
+        Ok(self.0.values().cloned())
+    }
+}
+
+impl From<Labels> for MemoryLabelRepository {
+    fn from(labels: Labels) -> Self {
+        let labels = labels
+            .into_vec()
+            .into_iter()
+            .map(|label| (label.ref_(), label));
+        Self(labels.collect())
+    }
+}
+
+/// The key a label is stored under in backends that use string keys
+#[cfg_attr(not(feature = "redb"), allow(dead_code))]
+pub(crate) fn storage_key(ref_: &LabelRef) -> String {
+    // the type prefix keeps inputs and outputs with the same outpoint apart
+    format!("{}:{ref_}", ref_.type_tag())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{InputRecord, OutputRecord};
+
+    #[test]
+    fn memory_repository_put_get_delete() {
+        let outpoint = bitcoin::OutPoint::from_str(
+            "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:1",
+        )
+        .unwrap();
+
+        let input = Label::Input(InputRecord {
+            ref_: outpoint,
+            label: Some("Input".to_string()),
+        });
+        let output = Label::Output(OutputRecord {
+            ref_: outpoint,
+            label: Some("Output".to_string()),
+            spendable: None,
+        });
+
+        let mut repository = MemoryLabelRepository::new();
+        assert_eq!(repository.put(input.clone()).unwrap(), None);
+        assert_eq!(repository.put(output.clone()).unwrap(), None);
+        assert_eq!(repository.len(), 2);
+
+        assert_eq!(repository.get(&input.ref_()).unwrap(), Some(input.clone()));
+        assert_eq!(repository.delete(&output.ref_()).unwrap(), Some(output));
+        assert_eq!(repository.get(&LabelRef::Output(outpoint)).unwrap(), None);
+        assert_eq!(repository.export().unwrap(), Labels::new(vec![input]));
+    }
+
+    #[test]
+    fn memory_repository_import() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let expected = labels.clone().into_map();
+
+        let mut repository = MemoryLabelRepository::new();
+        repository.import(labels).unwrap();
+
+        assert_eq!(repository.len(), expected.len());
+        for (ref_, label) in expected {
+            assert_eq!(repository.get(&ref_).unwrap(), Some(label));
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use redb::{Database, ReadableTable as _, StorageBackend, TableDefinition};
+
+use super::{storage_key, LabelRepository};
+use crate::{error::RepositoryError, Label, LabelRef, Labels};
+
+/// Labels are stored as JSON records keyed by `<type>:<ref>`
+const LABELS: TableDefinition<&str, &str> = TableDefinition::new("bip329_labels");
+
+/// A [`LabelRepository`] backed by a [redb](https://docs.rs/redb) database.
+///
+/// Labels are iterated in the order of their `<type>:<ref>` storage key.
+pub struct RedbLabelRepository(Database);
+
+impl RedbLabelRepository {
+    /// Open or create a label database at the given path.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        let database = Database::create(path)?;
+        Self::from_database(database)
+    }
+
+    /// Create a label database using a custom redb storage backend.
+    pub fn create_with_backend(backend: impl StorageBackend) -> Result<Self, RepositoryError> {
+        let database = Database::builder().create_with_backend(backend)?;
+        Self::from_database(database)
+    }
+
+    /// Use an existing redb database, creating the labels table if needed.
+    pub fn from_database(database: Database) -> Result<Self, RepositoryError> {
+        let txn = database.begin_write()?;
+        txn.open_table(LABELS)?;
+        txn.commit()?;
+
+        Ok(Self(database))
+    }
+
+    /// Get the underlying redb database.
+    pub fn into_database(self) -> Database {
+        self.0
+    }
+}
+
+impl LabelRepository for RedbLabelRepository {
+    type Error = RepositoryError;
+
+    fn get(&self, ref_: &LabelRef) -> Result<Option<Label>, Self::Error> {
+        let txn = self.0.begin_read()?;
+        let table = txn.open_table(LABELS)?;
+
+        let Some(value) = table.get(storage_key(ref_).as_str())? else {
+            return Ok(None);
+        };
+
+        Ok(Some(serde_json::from_str(value.value())?))
+    }
+
+    fn put(&mut self, label: Label) -> Result<Option<Label>, Self::Error> {
+        let txn = self.0.begin_write()?;
+        let previous = insert_label(&txn, &label)?;
+        txn.commit()?;
+
+        Ok(previous)
+    }
+
+    fn delete(&mut self, ref_: &LabelRef) -> Result<Option<Label>, Self::Error> {
+        let txn = self.0.begin_write()?;
+        let previous = {
+            let mut table = txn.open_table(LABELS)?;
+            let previous = table.remove(storage_key(ref_).as_str())?;
+            previous
+                .map(|value| serde_json::from_str(value.value()))
+                .transpose()?
+        };
+        txn.commit()?;
+
+        Ok(previous)
+    }
+
+    fn iter(&self) -> Result<impl Iterator<Item = Label> + '_, Self::Error> {
+        let txn = self.0.begin_read()?;
+        let table = txn.open_table(LABELS)?;
+
+        // read guards borrow the transaction, so labels are collected up front
+        let labels = table
+            .iter()?
+            .map(|entry| {
+                let (_key, value) = entry?;
+                Ok(serde_json::from_str(value.value())?)
+            })
+            .collect::<Result<Vec<Label>, RepositoryError>>()?;
+
+        Ok(labels.into_iter())
+    }
+
+    /// Insert or replace every label in a single write transaction.
+    fn import(&mut self, labels: Labels) -> Result<(), Self::Error> {
+        let txn = self.0.begin_write()?;
+        for label in labels.iter() {
+            insert_label(&txn, label)?;
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+}
+
+fn insert_label(
+    txn: &redb::WriteTransaction,
+    label: &Label,
+) -> Result<Option<Label>, RepositoryError> {
+    let key = storage_key(&label.ref_());
+    let value = serde_json::to_string(label)?;
+
+    let mut table = txn.open_table(LABELS)?;
+    let previous = table.insert(key.as_str(), value.as_str())?;
+
+    let previous = previous
+        .map(|value| serde_json::from_str(value.value()))
+        .transpose()?;
+
+    Ok(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use redb::backends::InMemoryBackend;
+
+    use super::*;
+    use crate::repository::MemoryLabelRepository;
+
+    #[test]
+    fn redb_repository_matches_memory_repository() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let mut redb = RedbLabelRepository::create_with_backend(InMemoryBackend::new()).unwrap();
+        redb.import(labels.clone()).unwrap();
+
+        let memory = MemoryLabelRepository::from(labels.clone());
+
+        let mut exported = redb.export().unwrap();
+        exported.sort();
+        let mut expected = memory.export().unwrap();
+        expected.sort();
+        assert_eq!(exported, expected);
+
+        let label = labels[0].clone();
+        assert_eq!(redb.get(&label.ref_()).unwrap(), Some(label.clone()));
+        assert_eq!(redb.delete(&label.ref_()).unwrap(), Some(label.clone()));
+        assert_eq!(redb.get(&label.ref_()).unwrap(), None);
+        assert_eq!(redb.put(label.clone()).unwrap(), None);
+        assert_eq!(redb.put(label.clone()).unwrap(), Some(label));
+    }
+}
@@ -0,0 +1,515 @@
+//! Module for editing labels with undo and redo.
+//!
+//! [`EditSession`] wraps a [`Labels`] list and records each edit made through it with
+//! the record before and after the edit, so any edit can be undone and redone. Edits
+//! made inside a transaction are undone and redone together as one step, and
+//! [`EditSession::diff`] gives the net change of the whole session.
+//!
+//! ```rust
+//! use bip329::{session::EditSession, Labels};
+//!
+//! let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+//! let mut session = EditSession::new(labels.clone());
+//!
+//! let ref_ = labels[0].ref_();
+//! session.update_label(&ref_, Some("Rent".to_string())).unwrap();
+//! session.remove(&ref_).unwrap();
+//!
+//! session.undo();
+//! assert_eq!(session.labels()[0].label(), Some("Rent"));
+//! assert_eq!(session.diff().changed.len(), 1);
+//!
+//! session.undo();
+//! assert_eq!(*session.labels(), labels);
+//! assert!(session.diff().is_empty());
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bitcoin::OutPoint;
+
+use crate::{error::SessionError, Label, LabelRef, Labels};
+
+/// An edit to a single position in the list, with the record before and after
+#[derive(Clone, Debug)]
+struct Change {
+    position: usize,
+    before: Option<Label>,
+    after: Option<Label>,
+}
+
+impl Change {
+    /// Put `to` at the position in place of `from`, for redoing or undoing the change.
+    fn swap(labels: &mut Labels, position: usize, to: &Option<Label>, from: &Option<Label>) {
+        match (to, from) {
+            (Some(label), Some(_)) => labels[position] = label.clone(),
+            (Some(label), None) => labels.insert(position, label.clone()),
+            (None, Some(_)) => {
+                labels.remove(position);
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn redo(&self, labels: &mut Labels) {
+        Self::swap(labels, self.position, &self.after, &self.before);
+    }
+
+    fn undo(&self, labels: &mut Labels) {
+        Self::swap(labels, self.position, &self.before, &self.after);
+    }
+}
+
+/// The net change of an edit session, with records ordered by [`LabelRef`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SessionDiff {
+    /// Records that did not exist when the session started
+    pub added: Vec<Label>,
+    /// Records that existed when the session started and were removed
+    pub removed: Vec<Label>,
+    /// Records that were edited, as `(before, after)`
+    pub changed: Vec<(Label, Label)>,
+}
+
+impl SessionDiff {
+    /// Whether the session made no net change.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Labels being edited, with multi-level undo and redo
+///
+/// Edits change the first record with a given reference, like the other label
+/// editing types. An edit that leaves the record unchanged is not recorded.
+#[derive(Clone, Debug, Default)]
+pub struct EditSession {
+    original: Labels,
+    labels: Labels,
+    undo: Vec<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    /// The changes of each open transaction, innermost last
+    transactions: Vec<Vec<Change>>,
+}
+
+impl EditSession {
+    /// Start an edit session on the labels.
+    pub fn new(labels: Labels) -> Self {
+        Self {
+            original: labels.clone(),
+            labels,
+            ..Default::default()
+        }
+    }
+
+    /// The labels with all edits so far applied.
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    /// The labels as they were when the session started.
+    pub fn original(&self) -> &Labels {
+        &self.original
+    }
+
+    /// End the session, returning the edited labels.
+    pub fn into_labels(self) -> Labels {
+        self.labels
+    }
+
+    /// Insert a record, replacing the record with the same reference, and return the
+    /// replaced record.
+    pub fn insert(&mut self, label: Label) -> Option<Label> {
+        let ref_ = label.ref_();
+
+        match self.position(&ref_) {
+            Some(position) => {
+                let before = self.labels[position].clone();
+                self.record(position, Some(before.clone()), Some(label));
+                Some(before)
+            }
+            None => {
+                self.record(self.labels.len(), None, Some(label));
+                None
+            }
+        }
+    }
+
+    /// Set or clear the label of a record, and return the previous label.
+    pub fn update_label(
+        &mut self,
+        ref_: &LabelRef,
+        label: Option<String>,
+    ) -> Result<Option<String>, SessionError> {
+        let position = self.existing(ref_)?;
+        let before = self.labels[position].clone();
+
+        let mut after = before.clone();
+        let previous = std::mem::replace(after.label_mut(), label);
+
+        self.record(position, Some(before), Some(after));
+        Ok(previous)
+    }
+
+    /// Toggle whether an output is spendable, and return the new value.
+    pub fn toggle_spendable(&mut self, ref_: OutPoint) -> Result<bool, SessionError> {
+        let position = self.existing(&LabelRef::Output(ref_))?;
+        let before = self.labels[position].clone();
+
+        let mut after = before.clone();
+        let Label::Output(record) = &mut after else {
+            unreachable!("output refs only match output records");
+        };
+        let spendable = !record.spendable();
+        record.spendable = Some(spendable);
+
+        self.record(position, Some(before), Some(after));
+        Ok(spendable)
+    }
+
+    /// Remove a record, and return it.
+    pub fn remove(&mut self, ref_: &LabelRef) -> Result<Label, SessionError> {
+        let position = self.existing(ref_)?;
+        let before = self.labels[position].clone();
+
+        self.record(position, Some(before.clone()), None);
+        Ok(before)
+    }
+
+    /// Open a transaction. Until it is committed, the edits made are grouped into a
+    /// single undo step.
+    ///
+    /// Transactions can be nested, a committed inner transaction becomes part of the
+    /// enclosing one. Nothing can be undone or redone while a transaction is open.
+    pub fn begin_transaction(&mut self) {
+        self.transactions.push(Vec::new());
+    }
+
+    /// Commit the innermost open transaction.
+    pub fn commit_transaction(&mut self) -> Result<(), SessionError> {
+        let changes = self.transactions.pop().ok_or(SessionError::NoTransaction)?;
+
+        match self.transactions.last_mut() {
+            Some(enclosing) => enclosing.extend(changes),
+            None if changes.is_empty() => {}
+            None => self.push_undo(changes),
+        }
+
+        Ok(())
+    }
+
+    /// Revert the edits of the innermost open transaction and close it.
+    pub fn rollback_transaction(&mut self) -> Result<(), SessionError> {
+        let changes = self.transactions.pop().ok_or(SessionError::NoTransaction)?;
+
+        for change in changes.iter().rev() {
+            change.undo(&mut self.labels);
+        }
+
+        Ok(())
+    }
+
+    /// Run the edits in a transaction, committing it if they succeed and rolling it
+    /// back if they fail.
+    pub fn transaction<T, E>(
+        &mut self,
+        edits: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.begin_transaction();
+        let result = edits(self);
+
+        // the transaction opened above is still the innermost one, as long as the
+        // edits closed every transaction they opened
+        if result.is_ok() {
+            self.commit_transaction().expect("transaction is open");
+        } else {
+            self.rollback_transaction().expect("transaction is open");
+        }
+
+        result
+    }
+
+    /// Whether there is an edit or transaction to undo.
+    pub fn can_undo(&self) -> bool {
+        self.transactions.is_empty() && !self.undo.is_empty()
+    }
+
+    /// Whether there is an undone edit or transaction to redo.
+    pub fn can_redo(&self) -> bool {
+        self.transactions.is_empty() && !self.redo.is_empty()
+    }
+
+    /// Undo the last edit or transaction, returning `false` if there was nothing to
+    /// undo.
+    pub fn undo(&mut self) -> bool {
+        if !self.transactions.is_empty() {
+            return false;
+        }
+
+        let Some(changes) = self.undo.pop() else {
+            return false;
+        };
+
+        for change in changes.iter().rev() {
+            change.undo(&mut self.labels);
+        }
+
+        self.redo.push(changes);
+        true
+    }
+
+    /// Redo the last undone edit or transaction, returning `false` if there was
+    /// nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        if !self.transactions.is_empty() {
+            return false;
+        }
+
+        let Some(changes) = self.redo.pop() else {
+            return false;
+        };
+
+        for change in &changes {
+            change.redo(&mut self.labels);
+        }
+
+        self.undo.push(changes);
+        true
+    }
+
+    /// The net change from the labels the session started with to the current labels.
+    pub fn diff(&self) -> SessionDiff {
+        let before = by_ref(&self.original);
+        let after = by_ref(&self.labels);
+        let refs = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+
+        let mut diff = SessionDiff::default();
+
+        for ref_ in refs {
+            match (before.get(ref_), after.get(ref_)) {
+                (None, Some(label)) => diff.added.push((*label).clone()),
+                (Some(label), None) => diff.removed.push((*label).clone()),
+                (Some(before), Some(after)) if before != after => {
+                    diff.changed.push(((*before).clone(), (*after).clone()));
+                }
+                _ => {}
+            }
+        }
+
+        diff
+    }
+
+    fn position(&self, ref_: &LabelRef) -> Option<usize> {
+        self.labels.iter().position(|label| label.ref_() == *ref_)
+    }
+
+    fn existing(&self, ref_: &LabelRef) -> Result<usize, SessionError> {
+        self.position(ref_)
+            .ok_or_else(|| SessionError::MissingLabel(format!("{} {ref_}", ref_.type_tag())))
+    }
+
+    /// Apply a change and record it in the open transaction or as its own undo step.
+    fn record(&mut self, position: usize, before: Option<Label>, after: Option<Label>) {
+        if before == after {
+            return;
+        }
+
+        let change = Change {
+            position,
+            before,
+            after,
+        };
+        change.redo(&mut self.labels);
+
+        match self.transactions.last_mut() {
+            Some(transaction) => transaction.push(change),
+            None => self.push_undo(vec![change]),
+        }
+    }
+
+    /// Record a new undo step, which the undone steps can no longer be redone after.
+    fn push_undo(&mut self, changes: Vec<Change>) {
+        self.undo.push(changes);
+        self.redo.clear();
+    }
+}
+
+/// The first record for each reference, matching the record edits change
+fn by_ref(labels: &Labels) -> BTreeMap<LabelRef, &Label> {
+    let mut map = BTreeMap::new();
+
+    for label in labels.iter() {
+        map.entry(label.ref_()).or_insert(label);
+    }
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::TransactionRecord;
+
+    const TXID: &str = "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd";
+
+    fn outpoint(vout: u32) -> OutPoint {
+        OutPoint::from_str(&format!("{TXID}:{vout}")).unwrap()
+    }
+
+    fn tx_ref() -> LabelRef {
+        LabelRef::Txid(bitcoin::Txid::from_str(TXID).unwrap())
+    }
+
+    fn test_vector() -> Labels {
+        Labels::try_from_file("tests/data/test_vector.jsonl").unwrap()
+    }
+
+    #[test]
+    fn edits_are_undone_and_redone_in_order() {
+        let labels = test_vector();
+        let mut session = EditSession::new(labels.clone());
+
+        let previous = session.update_label(&tx_ref(), Some("Rent".to_string()));
+        assert_eq!(previous.unwrap().as_deref(), Some("Transaction"));
+        assert_eq!(session.toggle_spendable(outpoint(1)).unwrap(), true);
+        let removed = session.remove(&labels[1].ref_()).unwrap();
+        assert_eq!(removed, labels[1]);
+
+        let edited = session.labels().clone();
+        assert_eq!(edited.len(), labels.len() - 1);
+        assert_eq!(edited[0].label(), Some("Rent"));
+
+        assert!(session.undo());
+        assert_eq!(session.labels()[1], labels[1]);
+        assert!(session.undo());
+        assert!(session.undo());
+        assert!(!session.undo());
+        assert_eq!(*session.labels(), labels);
+
+        while session.redo() {}
+        assert_eq!(*session.labels(), edited);
+        assert!(!session.can_redo());
+    }
+
+    #[test]
+    fn insert_replaces_and_appends() {
+        let labels = test_vector();
+        let mut session = EditSession::new(labels.clone());
+
+        let replacement = Label::Transaction(TransactionRecord {
+            ref_: bitcoin::Txid::from_str(TXID).unwrap(),
+            label: Some("Replaced".to_string()),
+            origin: None,
+        });
+        assert_eq!(session.insert(replacement.clone()), Some(labels[0].clone()));
+        assert_eq!(session.labels()[0], replacement);
+
+        let output = Label::from_ref(LabelRef::Output(outpoint(9)));
+        assert_eq!(session.insert(output.clone()), None);
+        assert_eq!(session.labels().iter().last(), Some(&output));
+
+        session.undo();
+        session.undo();
+        assert_eq!(*session.labels(), labels);
+    }
+
+    #[test]
+    fn new_edits_clear_redo_and_no_op_edits_are_not_recorded() {
+        let mut session = EditSession::new(test_vector());
+
+        session
+            .update_label(&tx_ref(), Some("Rent".to_string()))
+            .unwrap();
+        session.undo();
+        assert!(session.can_redo());
+
+        session
+            .update_label(&tx_ref(), Some("Transaction".to_string()))
+            .unwrap();
+        assert!(session.can_redo());
+        assert!(!session.can_undo());
+
+        session.update_label(&tx_ref(), None).unwrap();
+        assert!(!session.can_redo());
+        assert!(session.can_undo());
+    }
+
+    #[test]
+    fn transactions_are_undone_as_one_step() {
+        let labels = test_vector();
+        let mut session = EditSession::new(labels.clone());
+
+        session.begin_transaction();
+        session.update_label(&tx_ref(), None).unwrap();
+        session.begin_transaction();
+        session.toggle_spendable(outpoint(1)).unwrap();
+        session.commit_transaction().unwrap();
+        assert!(!session.can_undo());
+        session.commit_transaction().unwrap();
+
+        let edited = session.labels().clone();
+        assert!(session.undo());
+        assert_eq!(*session.labels(), labels);
+        assert!(!session.can_undo());
+
+        assert!(session.redo());
+        assert_eq!(*session.labels(), edited);
+
+        assert!(matches!(
+            session.commit_transaction(),
+            Err(SessionError::NoTransaction)
+        ));
+    }
+
+    #[test]
+    fn failed_transactions_are_rolled_back() {
+        let labels = test_vector();
+        let mut session = EditSession::new(labels.clone());
+
+        session.remove(&labels[1].ref_()).unwrap();
+        let edited = session.labels().clone();
+
+        let result = session.transaction(|session| {
+            session.update_label(&tx_ref(), None)?;
+            session.remove(&labels[1].ref_())
+        });
+
+        let error = result.unwrap_err();
+        assert!(matches!(error, SessionError::MissingLabel(ref_) if ref_.starts_with("addr ")));
+        assert_eq!(*session.labels(), edited);
+
+        session.undo();
+        assert_eq!(*session.labels(), labels);
+    }
+
+    #[test]
+    fn diff_is_the_net_change() {
+        let labels = test_vector();
+        let mut session = EditSession::new(labels.clone());
+
+        session
+            .update_label(&tx_ref(), Some("Rent".to_string()))
+            .unwrap();
+        session.toggle_spendable(outpoint(1)).unwrap();
+        session.toggle_spendable(outpoint(1)).unwrap();
+        session.remove(&labels[1].ref_()).unwrap();
+        session.insert(Label::from_ref(LabelRef::Output(outpoint(9))));
+
+        let diff = session.diff();
+        assert_eq!(
+            diff.added,
+            vec![Label::from_ref(LabelRef::Output(outpoint(9)))]
+        );
+        assert_eq!(diff.removed, vec![labels[1].clone()]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].0, labels[0]);
+        assert_eq!(diff.changed[0].1.label(), Some("Rent"));
+
+        while session.undo() {}
+        assert!(session.diff().is_empty());
+    }
+}
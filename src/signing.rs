@@ -0,0 +1,447 @@
+//! Module for signing and verifying label exports.
+//!
+//! Requires the `signing` feature. Signatures are detached, they are stored next to
+//! the label file and cover the [canonical export](Labels::export_canonical), so they
+//! stay valid when the records are reordered or reformatted.
+//!
+//! Two schemes are supported:
+//!
+//! - [`LabelSignature::sign_with_key`]: a BIP340 Schnorr signature by a BIP32 key,
+//!   over the tagged hash `SHA256(SHA256(tag) || SHA256(tag) || export)` where `tag`
+//!   is the ASCII string `bip329/signed-labels`. The signature records the key's
+//!   public key and fingerprint.
+//! - [`LabelSignature::sign_with_address`]: a [BIP322](https://github.com/bitcoin/bips/blob/master/bip-0322.mediawiki)
+//!   simple signature of the canonical export by a P2WPKH or P2TR address.
+//!
+//! Signature files are JSON objects:
+//!
+//! ```json
+//! {"scheme":"bip32","fingerprint":"3442193e","public_key":"0339a3...","signature":"5f0e..."}
+//! {"scheme":"bip322","address":"bc1q...","fingerprint":"3442193e","signature":"AkcwRAIg..."}
+//! ```
+
+use std::path::Path;
+
+use bitcoin::{
+    absolute::LockTime,
+    address::NetworkUnchecked,
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine as _},
+    bip32::{Fingerprint, Xpriv, Xpub},
+    consensus,
+    hashes::{sha256, Hash as _, HashEngine as _},
+    key::{Keypair, TapTweak as _},
+    opcodes::{all::OP_RETURN, OP_0},
+    script::{Builder, PushBytesBuf},
+    secp256k1::{schnorr, Message, PublicKey, Secp256k1},
+    sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType},
+    transaction::Version,
+    Address, Amount, CompressedPublicKey, OutPoint, PrivateKey, ScriptBuf, Sequence, Transaction,
+    TxIn, TxOut, Txid, Witness, XOnlyPublicKey,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::SignatureError, Labels};
+
+const LABELS_TAG: &[u8] = b"bip329/signed-labels";
+
+const BIP322_TAG: &[u8] = b"BIP0322-signed-message";
+
+/// A detached signature over the canonical export of a label set.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "scheme", rename_all = "lowercase")]
+pub enum LabelSignature {
+    /// A BIP340 Schnorr signature by a BIP32 key
+    Bip32 {
+        /// The fingerprint of the signing key
+        fingerprint: Fingerprint,
+        /// The public key of the signing key
+        public_key: PublicKey,
+        /// The signature
+        signature: schnorr::Signature,
+    },
+    /// A BIP322 simple signature by an address
+    Bip322 {
+        /// The signing address
+        address: Address<NetworkUnchecked>,
+        /// The fingerprint of the signing wallet, as claimed by the signer
+        ///
+        /// Not covered by the signature, only use it to look up the signer.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fingerprint: Option<Fingerprint>,
+        /// The base64 encoded witness of the BIP322 `to_sign` transaction
+        signature: String,
+    },
+}
+
+impl LabelSignature {
+    /// Sign the canonical export of the labels with the private key of a BIP32 key.
+    pub fn sign_with_key(labels: &Labels, xprv: &Xpriv) -> Result<Self, SignatureError> {
+        let secp = Secp256k1::new();
+        let message = Message::from_digest(labels_digest(labels)?);
+        let keypair = Keypair::from_secret_key(&secp, &xprv.private_key);
+
+        Ok(Self::Bip32 {
+            fingerprint: xprv.fingerprint(&secp),
+            public_key: keypair.public_key(),
+            signature: secp.sign_schnorr_no_aux_rand(&message, &keypair),
+        })
+    }
+
+    /// Sign the canonical export of the labels with the key of a P2WPKH or P2TR address.
+    ///
+    /// P2TR addresses must use the key path, with no script tree. The fingerprint is
+    /// stored alongside the signature as signer metadata.
+    pub fn sign_with_address(
+        labels: &Labels,
+        private_key: &PrivateKey,
+        address: &Address,
+        fingerprint: Option<Fingerprint>,
+    ) -> Result<Self, SignatureError> {
+        let export = labels.export_canonical()?;
+        let witness = bip322::sign(export.as_bytes(), private_key, address)?;
+
+        Ok(Self::Bip322 {
+            address: address.as_unchecked().clone(),
+            fingerprint,
+            signature: BASE64.encode(consensus::serialize(&witness)),
+        })
+    }
+
+    /// Verify the signature against the canonical export of the labels.
+    ///
+    /// This only proves the labels were signed by the key or address recorded in the
+    /// signature. Use [`LabelSignature::verify_signer`] or compare
+    /// [`LabelSignature::address`] with a trusted address to check who that is.
+    pub fn verify(&self, labels: &Labels) -> Result<(), SignatureError> {
+        match self {
+            Self::Bip32 {
+                fingerprint,
+                public_key,
+                signature,
+            } => {
+                if key_fingerprint(public_key) != *fingerprint {
+                    return Err(SignatureError::FingerprintMismatch);
+                }
+
+                let secp = Secp256k1::verification_only();
+                let message = Message::from_digest(labels_digest(labels)?);
+                let (public_key, _) = public_key.x_only_public_key();
+
+                secp.verify_schnorr(signature, &message, &public_key)
+                    .map_err(|_| SignatureError::InvalidSignature)
+            }
+            Self::Bip322 {
+                address, signature, ..
+            } => {
+                let witness = BASE64
+                    .decode(signature)
+                    .ok()
+                    .and_then(|bytes| consensus::deserialize::<Witness>(&bytes).ok())
+                    .ok_or(SignatureError::InvalidEncoding)?;
+
+                let export = labels.export_canonical()?;
+                let address = address.clone().assume_checked();
+                bip322::verify(export.as_bytes(), &address, &witness)
+            }
+        }
+    }
+
+    /// Verify the signature, and that it was made by the given extended public key.
+    ///
+    /// Only [`LabelSignature::Bip32`] signatures can match an extended public key.
+    pub fn verify_signer(&self, labels: &Labels, xpub: &Xpub) -> Result<(), SignatureError> {
+        match self {
+            Self::Bip32 { public_key, .. } if *public_key == xpub.public_key => self.verify(labels),
+            _ => Err(SignatureError::SignerMismatch),
+        }
+    }
+
+    /// The fingerprint of the signer, if known.
+    pub fn fingerprint(&self) -> Option<Fingerprint> {
+        match self {
+            Self::Bip32 { fingerprint, .. } => Some(*fingerprint),
+            Self::Bip322 { fingerprint, .. } => *fingerprint,
+        }
+    }
+
+    /// The signing address, for BIP322 signatures.
+    pub fn address(&self) -> Option<&Address<NetworkUnchecked>> {
+        match self {
+            Self::Bip32 { .. } => None,
+            Self::Bip322 { address, .. } => Some(address),
+        }
+    }
+
+    /// Create a new LabelSignature from its JSON encoding.
+    pub fn try_from_str(signature: &str) -> Result<Self, SignatureError> {
+        Ok(serde_json::from_str(signature)?)
+    }
+
+    /// Export the signature to its JSON encoding.
+    pub fn export(&self) -> Result<String, SignatureError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Read a signature from a file.
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self, SignatureError> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::try_from_str(contents.trim())
+    }
+
+    /// Write the signature to a file.
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<(), SignatureError> {
+        let mut contents = self.export()?;
+        contents.push('\n');
+
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+impl Labels {
+    /// Sign the labels with a BIP32 key, see [`LabelSignature::sign_with_key`].
+    pub fn sign(&self, xprv: &Xpriv) -> Result<LabelSignature, SignatureError> {
+        LabelSignature::sign_with_key(self, xprv)
+    }
+
+    /// Verify a detached signature over the labels, see [`LabelSignature::verify`].
+    pub fn verify_signature(&self, signature: &LabelSignature) -> Result<(), SignatureError> {
+        signature.verify(self)
+    }
+}
+
+fn labels_digest(labels: &Labels) -> Result<[u8; 32], SignatureError> {
+    let export = labels.export_canonical()?;
+    Ok(tagged_hash(LABELS_TAG, export.as_bytes()))
+}
+
+fn tagged_hash(tag: &[u8], message: &[u8]) -> [u8; 32] {
+    let tag = sha256::Hash::hash(tag);
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(message);
+
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// The BIP32 fingerprint of a public key, the first 4 bytes of its HASH160
+fn key_fingerprint(public_key: &PublicKey) -> Fingerprint {
+    let hash = bitcoin::PublicKey::new(*public_key).pubkey_hash();
+
+    Fingerprint::from(<[u8; 4]>::try_from(&hash[..4]).expect("4 bytes"))
+}
+
+/// BIP322 simple signatures for P2WPKH and key path P2TR addresses
+mod bip322 {
+    use super::*;
+
+    pub(super) fn sign(
+        message: &[u8],
+        private_key: &PrivateKey,
+        address: &Address,
+    ) -> Result<Witness, SignatureError> {
+        let secp = Secp256k1::new();
+        let script_pubkey = address.script_pubkey();
+        let to_spend = to_spend(message, &script_pubkey);
+        let mut to_sign = to_sign(to_spend.compute_txid());
+
+        if script_pubkey.is_p2wpkh() {
+            let public_key = CompressedPublicKey::from_private_key(&secp, private_key)
+                .map_err(|_| SignatureError::SignerMismatch)?;
+
+            if ScriptBuf::new_p2wpkh(&public_key.wpubkey_hash()) != script_pubkey {
+                return Err(SignatureError::SignerMismatch);
+            }
+
+            let sighash = SighashCache::new(&to_sign)
+                .p2wpkh_signature_hash(0, &script_pubkey, Amount::ZERO, EcdsaSighashType::All)
+                .map_err(|_| SignatureError::UnsupportedAddress)?;
+
+            // low R signatures, like Bitcoin Core, so signatures match other implementations
+            let signature = bitcoin::ecdsa::Signature::sighash_all(
+                secp.sign_ecdsa_low_r(&Message::from(sighash), &private_key.inner),
+            );
+
+            to_sign.input[0].witness = Witness::p2wpkh(&signature, &public_key.0);
+        } else if script_pubkey.is_p2tr() {
+            let keypair = Keypair::from_secret_key(&secp, &private_key.inner);
+            let (internal_key, _) = keypair.x_only_public_key();
+
+            if ScriptBuf::new_p2tr(&secp, internal_key, None) != script_pubkey {
+                return Err(SignatureError::SignerMismatch);
+            }
+
+            let prevouts = [TxOut {
+                value: Amount::ZERO,
+                script_pubkey,
+            }];
+            let sighash = SighashCache::new(&to_sign)
+                .taproot_key_spend_signature_hash(
+                    0,
+                    &Prevouts::All(&prevouts),
+                    TapSighashType::Default,
+                )
+                .map_err(|_| SignatureError::UnsupportedAddress)?;
+
+            let tweaked = Keypair::from(keypair.tap_tweak(&secp, None));
+            let signature = bitcoin::taproot::Signature {
+                signature: secp.sign_schnorr_no_aux_rand(&Message::from(sighash), &tweaked),
+                sighash_type: TapSighashType::Default,
+            };
+
+            to_sign.input[0].witness = Witness::p2tr_key_spend(&signature);
+        } else {
+            return Err(SignatureError::UnsupportedAddress);
+        }
+
+        Ok(to_sign.input.remove(0).witness)
+    }
+
+    pub(super) fn verify(
+        message: &[u8],
+        address: &Address,
+        witness: &Witness,
+    ) -> Result<(), SignatureError> {
+        let secp = Secp256k1::verification_only();
+        let script_pubkey = address.script_pubkey();
+        let to_spend = to_spend(message, &script_pubkey);
+        let mut to_sign = to_sign(to_spend.compute_txid());
+        to_sign.input[0].witness = witness.clone();
+
+        if script_pubkey.is_p2wpkh() {
+            let [signature, public_key] = witness_elements(witness)?;
+            let public_key = CompressedPublicKey::from_slice(public_key)
+                .map_err(|_| SignatureError::InvalidSignature)?;
+
+            if ScriptBuf::new_p2wpkh(&public_key.wpubkey_hash()) != script_pubkey {
+                return Err(SignatureError::InvalidSignature);
+            }
+
+            let signature = bitcoin::ecdsa::Signature::from_slice(signature)
+                .map_err(|_| SignatureError::InvalidSignature)?;
+            let sighash = SighashCache::new(&to_sign)
+                .p2wpkh_signature_hash(0, &script_pubkey, Amount::ZERO, signature.sighash_type)
+                .map_err(|_| SignatureError::InvalidSignature)?;
+
+            secp.verify_ecdsa(&Message::from(sighash), &signature.signature, &public_key.0)
+                .map_err(|_| SignatureError::InvalidSignature)
+        } else if script_pubkey.is_p2tr() {
+            let [signature] = witness_elements(witness)?;
+            let signature = bitcoin::taproot::Signature::from_slice(signature)
+                .map_err(|_| SignatureError::InvalidSignature)?;
+            let output_key = XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..])
+                .map_err(|_| SignatureError::InvalidSignature)?;
+
+            let prevouts = [TxOut {
+                value: Amount::ZERO,
+                script_pubkey,
+            }];
+            let sighash = SighashCache::new(&to_sign)
+                .taproot_key_spend_signature_hash(
+                    0,
+                    &Prevouts::All(&prevouts),
+                    signature.sighash_type,
+                )
+                .map_err(|_| SignatureError::InvalidSignature)?;
+
+            secp.verify_schnorr(&signature.signature, &Message::from(sighash), &output_key)
+                .map_err(|_| SignatureError::InvalidSignature)
+        } else {
+            Err(SignatureError::UnsupportedAddress)
+        }
+    }
+
+    fn witness_elements<const N: usize>(witness: &Witness) -> Result<[&[u8]; N], SignatureError> {
+        let elements = witness.iter().collect::<Vec<_>>();
+        elements
+            .try_into()
+            .map_err(|_| SignatureError::InvalidSignature)
+    }
+
+    fn to_spend(message: &[u8], script_pubkey: &ScriptBuf) -> Transaction {
+        let message_hash =
+            PushBytesBuf::try_from(tagged_hash(BIP322_TAG, message).to_vec()).expect("32 bytes");
+
+        Transaction {
+            version: Version(0),
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::all_zeros(), 0xFFFFFFFF),
+                script_sig: Builder::new()
+                    .push_opcode(OP_0)
+                    .push_slice(message_hash)
+                    .into_script(),
+                sequence: Sequence::ZERO,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::ZERO,
+                script_pubkey: script_pubkey.clone(),
+            }],
+        }
+    }
+
+    fn to_sign(to_spend: Txid) -> Transaction {
+        Transaction {
+            version: Version(0),
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(to_spend, 0),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ZERO,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::ZERO,
+                script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+            }],
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::str::FromStr as _;
+
+        use super::*;
+
+        // test vectors from BIP322
+        const WIF: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+        const ADDRESS: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+        const P2TR_ADDRESS: &str = "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3";
+
+        #[test]
+        fn p2wpkh_test_vectors() {
+            let private_key = PrivateKey::from_wif(WIF).unwrap();
+            let address = Address::from_str(ADDRESS).unwrap().assume_checked();
+
+            for (message, expected) in [
+                ("", "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI="),
+                ("Hello World", "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI="),
+            ] {
+                let witness = sign(message.as_bytes(), &private_key, &address).unwrap();
+                assert_eq!(BASE64.encode(consensus::serialize(&witness)), expected);
+
+                verify(message.as_bytes(), &address, &witness).unwrap();
+                assert!(verify(b"other", &address, &witness).is_err());
+            }
+        }
+
+        #[test]
+        fn p2tr_test_vector() {
+            let private_key = PrivateKey::from_wif(WIF).unwrap();
+            let address = Address::from_str(P2TR_ADDRESS).unwrap().assume_checked();
+            let message = b"Hello World";
+
+            // the vector signs with random aux data, so only its verification is reproducible
+            let expected = BASE64.decode("AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==").unwrap();
+            let expected: Witness = consensus::deserialize(&expected).unwrap();
+            verify(message, &address, &expected).unwrap();
+            assert!(verify(b"other", &address, &expected).is_err());
+
+            let witness = sign(message, &private_key, &address).unwrap();
+            verify(message, &address, &witness).unwrap();
+            assert!(verify(b"other", &address, &witness).is_err());
+        }
+    }
+}
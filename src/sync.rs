@@ -0,0 +1,672 @@
+//! Module for syncing labels between devices without conflicts.
+//!
+//! [`SyncedLabels`] is a state-based CRDT over label records. Every field of a record,
+//! its label, a transaction's origin, an output's spendable flag, and whether the record
+//! exists at all, is a last-writer-wins register stamped with a [`Timestamp`]: a
+//! Lamport clock and the [`DeviceId`] that made the edit. Deleting a record leaves a
+//! tombstone, so a device that has not seen the deletion can't bring the record back.
+//!
+//! [`SyncedLabels::merge`] keeps the newest write of every field, so it is commutative,
+//! associative and idempotent, and devices that have merged the same edits hold the same
+//! labels whatever order they merged them in. Edits to different records, or to
+//! different fields of the same record, are all kept. When two devices edit the same
+//! field, the edit with the higher clock wins, with ties broken by device id.
+//!
+//! The sync state is saved as BIP329 JSONL with an extra `sync` field on each record,
+//! holding the timestamps and a `deleted` flag on tombstones. Plain BIP329 records,
+//! without a `sync` field, load as edits older than any device's. Use
+//! [`SyncedLabels::export`] for plain BIP329 without the tombstones, to share with
+//! other wallets.
+//!
+//! ```rust
+//! use bip329::{sync::SyncedLabels, Labels};
+//!
+//! let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+//! let mut phone = SyncedLabels::from_labels("phone", labels);
+//! let synced = phone.export_synced().unwrap();
+//! let mut laptop = SyncedLabels::try_from_str("laptop", &synced).unwrap();
+//!
+//! // both devices edit offline
+//! let first = phone.labels()[0].ref_();
+//! let second = phone.labels()[1].ref_();
+//! phone.set_label(first.clone(), Some("Cold storage".to_string()));
+//! laptop.remove(&second);
+//!
+//! phone.merge(&laptop);
+//! laptop.merge(&phone);
+//!
+//! assert_eq!(phone.labels(), laptop.labels());
+//! assert_eq!(laptop.get(&first).unwrap().label(), Some("Cold storage"));
+//! assert_eq!(laptop.get(&second), None);
+//! ```
+
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use bitcoin::{OutPoint, Txid};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{ExportError, ParseError},
+    Label, LabelRef, Labels,
+};
+
+/// The key of the sync metadata added to each BIP329 record
+const SYNC_FIELD: &str = "sync";
+
+/// The highest clock accepted from a sync JSONL, leaving room for local edits to tick
+const MAX_CLOCK: u64 = u64::MAX / 2;
+
+/// Identifies the device that made an edit, must be unique among the syncing devices.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DeviceId(String);
+
+impl DeviceId {
+    /// Create a device id.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    /// The device id as a str.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for DeviceId {
+    fn from(id: &str) -> Self {
+        Self::new(id)
+    }
+}
+
+impl From<String> for DeviceId {
+    fn from(id: String) -> Self {
+        Self::new(id)
+    }
+}
+
+impl Display for DeviceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A Lamport timestamp, ordered by clock and then by device.
+///
+/// The default timestamp, with clock `0` and an empty device id, is older than any edit.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Timestamp {
+    /// The device's logical clock when it made the edit
+    pub clock: u64,
+    /// The device that made the edit
+    pub device: DeviceId,
+}
+
+/// A last-writer-wins register
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Register<T> {
+    value: T,
+    timestamp: Timestamp,
+}
+
+impl<T: Clone + Ord> Register<T> {
+    fn set(&mut self, value: T, timestamp: Timestamp) {
+        self.merge(&Register { value, timestamp });
+    }
+
+    fn merge(&mut self, other: &Self) {
+        // comparing values on equal timestamps keeps merging commutative, even for
+        // plain BIP329 records that all share the default timestamp
+        if (&other.timestamp, &other.value) > (&self.timestamp, &self.value) {
+            *self = other.clone();
+        }
+    }
+}
+
+/// The registers of a single record
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct SyncedRecord {
+    exists: Register<bool>,
+    label: Register<Option<String>>,
+    origin: Register<Option<String>>,
+    spendable: Register<Option<bool>>,
+}
+
+impl SyncedRecord {
+    fn merge(&mut self, other: &Self) {
+        self.exists.merge(&other.exists);
+        self.label.merge(&other.label);
+        self.origin.merge(&other.origin);
+        self.spendable.merge(&other.spendable);
+    }
+
+    fn to_label(&self, ref_: &LabelRef) -> Label {
+        let mut label = Label::from_ref(ref_.clone());
+        label.label_mut().clone_from(&self.label.value);
+
+        match &mut label {
+            Label::Transaction(record) => record.origin.clone_from(&self.origin.value),
+            Label::Output(record) => record.spendable = self.spendable.value,
+            _ => {}
+        }
+
+        label
+    }
+
+    fn clock(&self) -> u64 {
+        [
+            &self.exists.timestamp,
+            &self.label.timestamp,
+            &self.origin.timestamp,
+            &self.spendable.timestamp,
+        ]
+        .into_iter()
+        .map(|timestamp| timestamp.clock)
+        .max()
+        .unwrap_or_default()
+    }
+}
+
+/// The `sync` field of a record in the sync JSONL
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncFields {
+    #[serde(default)]
+    record: Timestamp,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    deleted: bool,
+    #[serde(default)]
+    label: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    origin: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spendable: Option<Timestamp>,
+}
+
+/// Labels that sync between devices by merging, see the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct SyncedLabels {
+    device: DeviceId,
+    clock: u64,
+    records: BTreeMap<LabelRef, SyncedRecord>,
+}
+
+impl SyncedLabels {
+    /// Create empty labels for the device.
+    pub fn new(device: impl Into<DeviceId>) -> Self {
+        Self {
+            device: device.into(),
+            clock: 0,
+            records: BTreeMap::new(),
+        }
+    }
+
+    /// Create synced labels from plain labels, as edits made on the device.
+    pub fn from_labels(device: impl Into<DeviceId>, labels: Labels) -> Self {
+        let mut synced = Self::new(device);
+        labels
+            .into_vec()
+            .into_iter()
+            .for_each(|label| synced.insert(label));
+
+        synced
+    }
+
+    /// Load the sync JSONL written by [`SyncedLabels::export_synced`] on the device.
+    ///
+    /// Plain BIP329 records are accepted, and records for the same ref are merged. Records
+    /// with a clock too close to `u64::MAX` for further edits are rejected.
+    pub fn try_from_str(device: impl Into<DeviceId>, jsonl: &str) -> Result<Self, ParseError> {
+        let mut synced = Self::new(device);
+
+        for (index, line) in jsonl.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (ref_, record) = parse_synced_record(line).map_err(|e| e.at_line(index + 1))?;
+            synced.clock = synced.clock.max(record.clock());
+            synced.records.entry(ref_).or_default().merge(&record);
+        }
+
+        Ok(synced)
+    }
+
+    /// Load a sync JSONL file on the device, see [`SyncedLabels::try_from_str`].
+    pub fn try_from_file(
+        device: impl Into<DeviceId>,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, ParseError> {
+        let jsonl = std::fs::read_to_string(path)?;
+        Self::try_from_str(device, &jsonl)
+    }
+
+    /// The device making the edits.
+    pub fn device(&self) -> &DeviceId {
+        &self.device
+    }
+
+    /// Insert or replace a record, overwriting every field.
+    pub fn insert(&mut self, label: Label) {
+        let timestamp = self.tick();
+        let record = self.records.entry(label.ref_()).or_default();
+
+        record.exists.set(true, timestamp.clone());
+        record
+            .label
+            .set(label.label().map(str::to_string), timestamp.clone());
+
+        match label {
+            Label::Transaction(label) => record.origin.set(label.origin, timestamp),
+            Label::Output(label) => record.spendable.set(label.spendable, timestamp),
+            _ => {}
+        }
+    }
+
+    /// Set the label of a record, creating the record if needed.
+    pub fn set_label(&mut self, ref_: LabelRef, label: Option<String>) {
+        let timestamp = self.tick();
+        let record = self.records.entry(ref_).or_default();
+
+        record.exists.set(true, timestamp.clone());
+        record.label.set(label, timestamp);
+    }
+
+    /// Set the origin of a transaction record, creating the record if needed.
+    pub fn set_origin(&mut self, txid: Txid, origin: Option<String>) {
+        let timestamp = self.tick();
+        let record = self.records.entry(LabelRef::Txid(txid)).or_default();
+
+        record.exists.set(true, timestamp.clone());
+        record.origin.set(origin, timestamp);
+    }
+
+    /// Set whether an output is spendable, creating the record if needed.
+    pub fn set_spendable(&mut self, outpoint: OutPoint, spendable: Option<bool>) {
+        let timestamp = self.tick();
+        let record = self.records.entry(LabelRef::Output(outpoint)).or_default();
+
+        record.exists.set(true, timestamp.clone());
+        record.spendable.set(spendable, timestamp);
+    }
+
+    /// Delete a record, leaving a tombstone, and return the deleted label.
+    pub fn remove(&mut self, ref_: &LabelRef) -> Option<Label> {
+        let removed = self.get(ref_)?;
+        let timestamp = self.tick();
+        let record = self.records.get_mut(ref_)?;
+
+        // clear the fields too, so they don't come back if the record is recreated
+        record.exists.set(false, timestamp.clone());
+        record.label.set(None, timestamp.clone());
+        record.origin.set(None, timestamp.clone());
+        record.spendable.set(None, timestamp);
+
+        Some(removed)
+    }
+
+    /// Get the label for the reference, `None` if it does not exist or was deleted.
+    pub fn get(&self, ref_: &LabelRef) -> Option<Label> {
+        let record = self.records.get(ref_)?;
+        record.exists.value.then(|| record.to_label(ref_))
+    }
+
+    /// Get an iterator over the labels that are not deleted, ordered by [`LabelRef`].
+    pub fn iter(&self) -> impl Iterator<Item = Label> + '_ {
+        self.records
+            .iter()
+            .filter(|(_, record)| record.exists.value)
+            .map(|(ref_, record)| record.to_label(ref_))
+    }
+
+    /// The labels that are not deleted, ordered by [`LabelRef`].
+    pub fn labels(&self) -> Labels {
+        Labels::new(self.iter().collect())
+    }
+
+    /// The number of labels that are not deleted.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if there are no labels, or all were deleted.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Merge the edits from another device, keeping the newest write of every field.
+    pub fn merge(&mut self, other: &SyncedLabels) {
+        for (ref_, record) in &other.records {
+            self.records.entry(ref_.clone()).or_default().merge(record);
+        }
+
+        // edits made after merging must be newer than everything merged
+        self.clock = self.clock.max(other.clock);
+    }
+
+    /// Export the labels as plain BIP329 JSONL, without tombstones or sync metadata.
+    pub fn export(&self) -> Result<String, ExportError> {
+        self.labels().export()
+    }
+
+    /// Export the sync state as BIP329 JSONL with a `sync` field on each record,
+    /// including tombstones.
+    pub fn export_synced(&self) -> Result<String, ExportError> {
+        self.records
+            .iter()
+            .try_fold(String::new(), |mut acc, (ref_, record)| {
+                acc.push_str(&synced_record(ref_, record)?);
+                acc.push('\n');
+                Ok(acc)
+            })
+    }
+
+    /// Export the sync state to a file, see [`SyncedLabels::export_synced`].
+    pub fn export_synced_to_file(&self, path: impl AsRef<Path>) -> Result<(), ExportError> {
+        std::fs::write(path, self.export_synced()?)?;
+        Ok(())
+    }
+
+    fn tick(&mut self) -> Timestamp {
+        self.clock += 1;
+
+        Timestamp {
+            clock: self.clock,
+            device: self.device.clone(),
+        }
+    }
+}
+
+fn synced_record(ref_: &LabelRef, record: &SyncedRecord) -> Result<String, ExportError> {
+    let Value::Object(mut fields) = serde_json::to_value(record.to_label(ref_))? else {
+        unreachable!("labels always serialize to a JSON object");
+    };
+
+    let sync = SyncFields {
+        record: record.exists.timestamp.clone(),
+        deleted: !record.exists.value,
+        label: record.label.timestamp.clone(),
+        origin: matches!(ref_, LabelRef::Txid(_)).then(|| record.origin.timestamp.clone()),
+        spendable: matches!(ref_, LabelRef::Output(_)).then(|| record.spendable.timestamp.clone()),
+    };
+
+    fields.insert(SYNC_FIELD.to_string(), serde_json::to_value(sync)?);
+    Ok(serde_json::to_string(&fields)?)
+}
+
+fn parse_synced_record(line: &str) -> Result<(LabelRef, SyncedRecord), ParseError> {
+    let mut fields: Map<String, Value> = serde_json::from_str(line)?;
+    let sync: SyncFields = match fields.remove(SYNC_FIELD) {
+        Some(sync) => serde_json::from_value(sync)?,
+        None => SyncFields::default(),
+    };
+
+    let label: Label = serde_json::from_value(Value::Object(fields))?;
+    let mut record = SyncedRecord {
+        exists: Register {
+            value: !sync.deleted,
+            timestamp: sync.record,
+        },
+        label: Register {
+            value: label.label().map(str::to_string),
+            timestamp: sync.label,
+        },
+        ..Default::default()
+    };
+
+    match &label {
+        Label::Transaction(label) => {
+            record.origin = Register {
+                value: label.origin.clone(),
+                timestamp: sync.origin.unwrap_or_default(),
+            };
+        }
+        Label::Output(label) => {
+            record.spendable = Register {
+                value: label.spendable,
+                timestamp: sync.spendable.unwrap_or_default(),
+            };
+        }
+        _ => {}
+    }
+
+    if record.clock() > MAX_CLOCK {
+        let message = format!(
+            "sync clock {} is above the maximum of {MAX_CLOCK}",
+            record.clock()
+        );
+        return Err(<serde_json::Error as serde::de::Error>::custom(message).into());
+    }
+
+    Ok((label.ref_(), record))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const TXID: &str = "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd";
+
+    fn outpoint(vout: u32) -> OutPoint {
+        OutPoint::from_str(&format!("{TXID}:{vout}")).unwrap()
+    }
+
+    fn base() -> SyncedLabels {
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        SyncedLabels::from_labels("base", labels)
+    }
+
+    fn device(name: &str, base: &SyncedLabels) -> SyncedLabels {
+        SyncedLabels::try_from_str(name, &base.export_synced().unwrap()).unwrap()
+    }
+
+    fn merged(a: &SyncedLabels, b: &SyncedLabels) -> SyncedLabels {
+        let mut merged = a.clone();
+        merged.merge(b);
+        merged
+    }
+
+    #[test]
+    fn offline_edits_to_different_records_converge() {
+        let base = base();
+        let mut phone = device("phone", &base);
+        let mut laptop = device("laptop", &base);
+
+        phone.set_label(LabelRef::Output(outpoint(1)), Some("Change".to_string()));
+        laptop.set_label(LabelRef::Output(outpoint(2)), Some("Savings".to_string()));
+
+        let phone_first = merged(&phone, &laptop);
+        let laptop_first = merged(&laptop, &phone);
+
+        assert_eq!(
+            phone_first.export_synced().unwrap(),
+            laptop_first.export_synced().unwrap()
+        );
+
+        let labels = phone_first.labels();
+        assert_eq!(labels.len(), base.len() + 1);
+
+        let get = |vout| phone_first.get(&LabelRef::Output(outpoint(vout))).unwrap();
+        assert_eq!(get(1).label(), Some("Change"));
+        assert_eq!(get(2).label(), Some("Savings"));
+    }
+
+    #[test]
+    fn edits_to_different_fields_are_kept() {
+        let base = base();
+        let mut phone = device("phone", &base);
+        let mut laptop = device("laptop", &base);
+
+        let ref_ = LabelRef::Output(outpoint(1));
+        phone.set_label(ref_.clone(), Some("Change".to_string()));
+        laptop.set_spendable(outpoint(1), Some(true));
+
+        let Some(Label::Output(output)) = merged(&phone, &laptop).get(&ref_) else {
+            panic!("expected an output label");
+        };
+
+        assert_eq!(output.label.as_deref(), Some("Change"));
+        assert_eq!(output.spendable, Some(true));
+    }
+
+    #[test]
+    fn conflicting_edits_pick_the_same_winner() {
+        let base = base();
+        let mut phone = device("phone", &base);
+        let mut laptop = device("laptop", &base);
+
+        let ref_ = LabelRef::Txid(Txid::from_str(TXID).unwrap());
+
+        // same clock, the higher device id wins
+        phone.set_label(ref_.clone(), Some("Phone".to_string()));
+        laptop.set_label(ref_.clone(), Some("Laptop".to_string()));
+
+        let phone_first = merged(&phone, &laptop);
+        let laptop_first = merged(&laptop, &phone);
+        assert_eq!(phone_first.get(&ref_).unwrap().label(), Some("Phone"));
+        assert_eq!(laptop_first.get(&ref_).unwrap().label(), Some("Phone"));
+
+        // an edit made after seeing the other device's edit wins
+        let mut laptop = laptop_first;
+        laptop.set_label(ref_.clone(), Some("Rent".to_string()));
+        assert_eq!(
+            merged(&phone, &laptop).get(&ref_).unwrap().label(),
+            Some("Rent")
+        );
+        assert_eq!(
+            merged(&laptop, &phone).get(&ref_).unwrap().label(),
+            Some("Rent")
+        );
+    }
+
+    #[test]
+    fn merge_is_idempotent_and_associative() {
+        let base = base();
+        let mut a = device("a", &base);
+        let mut b = device("b", &base);
+        let mut c = device("c", &base);
+
+        a.set_label(LabelRef::Output(outpoint(1)), Some("A".to_string()));
+        b.remove(&LabelRef::Output(outpoint(1)));
+        c.set_spendable(outpoint(1), Some(false));
+        c.set_label(LabelRef::Input(outpoint(0)), None);
+
+        let left = merged(&merged(&a, &b), &c);
+        let right = merged(&a, &merged(&b, &c));
+        let twice = merged(&merged(&left, &left), &b);
+
+        let export = left.export_synced().unwrap();
+        assert_eq!(export, right.export_synced().unwrap());
+        assert_eq!(export, twice.export_synced().unwrap());
+    }
+
+    #[test]
+    fn deleted_records_stay_deleted() {
+        let base = base();
+        let mut phone = device("phone", &base);
+        let stale = device("laptop", &base);
+
+        let ref_ = LabelRef::Output(outpoint(1));
+        assert!(phone.remove(&ref_).is_some());
+        assert_eq!(phone.remove(&ref_), None);
+
+        let mut laptop = merged(&stale, &phone);
+        assert_eq!(laptop.get(&ref_), None);
+        assert_eq!(merged(&phone, &stale).get(&ref_), None);
+        assert_eq!(laptop.len(), base.len() - 1);
+
+        // recreating after the delete starts from an empty record
+        laptop.set_label(ref_.clone(), Some("Back".to_string()));
+        let Some(Label::Output(output)) = merged(&phone, &laptop).get(&ref_) else {
+            panic!("expected an output label");
+        };
+
+        assert_eq!(output.label.as_deref(), Some("Back"));
+        assert_eq!(output.spendable, None);
+    }
+
+    #[test]
+    fn sync_jsonl_round_trips() {
+        let mut phone = device("phone", &base());
+        phone.remove(&LabelRef::Output(outpoint(1)));
+        phone.set_origin(Txid::from_str(TXID).unwrap(), None);
+
+        let jsonl = phone.export_synced().unwrap();
+        let loaded = SyncedLabels::try_from_str("phone", &jsonl).unwrap();
+        assert_eq!(loaded.export_synced().unwrap(), jsonl);
+        assert_eq!(loaded.labels(), phone.labels());
+
+        // the clock continues from the loaded edits
+        let mut loaded = loaded;
+        loaded.set_origin(Txid::from_str(TXID).unwrap(), Some("wpkh()".to_string()));
+        let merged = merged(&phone, &loaded);
+        let ref_ = LabelRef::Txid(Txid::from_str(TXID).unwrap());
+        let Some(Label::Transaction(tx)) = merged.get(&ref_) else {
+            panic!("expected a transaction label");
+        };
+
+        assert_eq!(tx.origin.as_deref(), Some("wpkh()"));
+    }
+
+    #[test]
+    fn clocks_near_the_maximum_are_rejected() {
+        let line = |clock: u64| {
+            format!(
+                r#"{{"type":"tx","ref":"{TXID}","label":"Rent","sync":{{"record":{{"clock":{clock},"device":"phone"}}}}}}"#
+            )
+        };
+
+        let error = SyncedLabels::try_from_str("laptop", &line(u64::MAX)).unwrap_err();
+        assert_eq!(error.line(), Some(1));
+        assert!(SyncedLabels::try_from_str("laptop", &line(MAX_CLOCK + 1)).is_err());
+
+        // local edits can still tick past the highest accepted clock
+        let mut laptop = SyncedLabels::try_from_str("laptop", &line(MAX_CLOCK)).unwrap();
+        laptop.set_label(LabelRef::Txid(Txid::from_str(TXID).unwrap()), None);
+        assert_eq!(laptop.clock, MAX_CLOCK + 1);
+    }
+
+    #[test]
+    fn sync_jsonl_is_readable_as_bip329() {
+        let mut phone = device("phone", &base());
+        phone.remove(&LabelRef::Output(outpoint(1)));
+
+        // plain parsers ignore the sync field, but see tombstones as records
+        let synced = Labels::try_from_str(&phone.export_synced().unwrap()).unwrap();
+        assert_eq!(synced.len(), phone.len() + 1);
+
+        let plain = Labels::try_from_str(&phone.export().unwrap()).unwrap();
+        assert_eq!(plain, phone.labels());
+    }
+
+    #[test]
+    fn plain_bip329_loads_as_oldest_edits() {
+        let jsonl = std::fs::read_to_string("tests/data/labels.jsonl").unwrap();
+        let mut phone = SyncedLabels::try_from_str("phone", &jsonl).unwrap();
+        let laptop = SyncedLabels::try_from_str("laptop", &jsonl).unwrap();
+
+        let expected = Labels::try_from_str(&jsonl).unwrap().into_map();
+        assert_eq!(phone.len(), expected.len());
+
+        let (ref_, _) = expected.iter().next().unwrap();
+        phone.set_label(ref_.clone(), Some("Edited".to_string()));
+        assert_eq!(
+            merged(&laptop, &phone).get(ref_).unwrap().label(),
+            Some("Edited")
+        );
+    }
+
+    #[test]
+    fn invalid_sync_jsonl_reports_the_line() {
+        let jsonl = format!(
+            "{{\"type\":\"tx\",\"ref\":\"{TXID}\"}}\n{{\"type\":\"tx\",\"ref\":\"{TXID}\",\"sync\":1}}"
+        );
+
+        let error = SyncedLabels::try_from_str("phone", &jsonl).unwrap_err();
+        assert_eq!(error.line(), Some(2));
+    }
+}
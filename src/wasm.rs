@@ -0,0 +1,127 @@
+//! WebAssembly bindings, behind the `wasm` feature.
+//!
+//! Labels are passed to and from JavaScript as arrays of plain objects in the BIP329
+//! JSON shape, e.g. `{ type: "tx", ref: "...", label: "..." }`. Errors are thrown as
+//! JavaScript `Error`s with the message of the Rust error.
+
+use std::collections::HashMap;
+
+use serde::Serialize as _;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    encryption::{EncryptedLabels, PassphraseOptions},
+    LabelParseOptions, LabelRef, Labels,
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const LABEL_TYPES: &str = r#"
+export type Label =
+  | { type: "tx"; ref: string; label?: string; origin?: string }
+  | { type: "addr"; ref: string; label?: string }
+  | { type: "pubkey"; ref: string; label?: string }
+  | { type: "input"; ref: string; label?: string | null }
+  | { type: "output"; ref: string; label?: string | null; spendable?: boolean }
+  | { type: "xpub"; ref: string; label?: string | null }
+  | { type: "spscan"; ref: string; label?: string | null };
+"#;
+
+fn to_js(labels: &Labels) -> Result<JsValue, JsError> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(labels.serialize(&serializer)?)
+}
+
+fn from_js(labels: JsValue) -> Result<Labels, JsError> {
+    Ok(serde_wasm_bindgen::from_value(labels)?)
+}
+
+/// Parse BIP329 JSONL into an array of labels.
+///
+/// Records with an unsupported `type` are rejected, unless `ignoreUnknownTypes` is set.
+#[wasm_bindgen(js_name = parseLabels, unchecked_return_type = "Label[]")]
+pub fn parse_labels(
+    jsonl: &str,
+    #[wasm_bindgen(js_name = ignoreUnknownTypes)] ignore_unknown_types: Option<bool>,
+) -> Result<JsValue, JsError> {
+    let options =
+        LabelParseOptions::default().ignore_unknown_types(ignore_unknown_types.unwrap_or(false));
+
+    to_js(&Labels::try_from_str_with_options(jsonl, options)?)
+}
+
+/// Export an array of labels as BIP329 JSONL.
+#[wasm_bindgen(js_name = exportLabels)]
+pub fn export_labels(
+    #[wasm_bindgen(unchecked_param_type = "Label[]")] labels: JsValue,
+) -> Result<String, JsError> {
+    Ok(from_js(labels)?.export()?)
+}
+
+/// Merge two arrays of labels.
+///
+/// Labels in `other` replace labels with the same type and ref in place, the rest are
+/// appended in order.
+#[wasm_bindgen(js_name = mergeLabels, unchecked_return_type = "Label[]")]
+pub fn merge_labels(
+    #[wasm_bindgen(unchecked_param_type = "Label[]")] labels: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Label[]")] other: JsValue,
+) -> Result<JsValue, JsError> {
+    let mut merged = from_js(labels)?.into_vec();
+    let mut positions: HashMap<LabelRef, usize> = merged
+        .iter()
+        .enumerate()
+        .map(|(position, label)| (label.ref_(), position))
+        .collect();
+
+    for label in from_js(other)?.into_vec() {
+        match positions.get(&label.ref_()) {
+            Some(&position) => merged[position] = label,
+            None => {
+                positions.insert(label.ref_(), merged.len());
+                merged.push(label);
+            }
+        }
+    }
+
+    to_js(&Labels::new(merged))
+}
+
+/// Encrypt an array of labels with a passphrase, returning ASCII armored age.
+///
+/// `workFactor` is the base 2 logarithm of the scrypt cost, age's default is used when
+/// unset. Lower values are faster in the browser, and cheaper to brute force.
+#[wasm_bindgen(js_name = encryptLabels)]
+pub fn encrypt_labels(
+    #[wasm_bindgen(unchecked_param_type = "Label[]")] labels: JsValue,
+    passphrase: &str,
+    #[wasm_bindgen(js_name = workFactor)] work_factor: Option<u8>,
+) -> Result<String, JsError> {
+    let labels = from_js(labels)?;
+
+    let options = match work_factor {
+        Some(work_factor) => PassphraseOptions::default().work_factor(work_factor),
+        None => PassphraseOptions::default(),
+    };
+
+    let encrypted = EncryptedLabels::encrypt_with_options(&labels, passphrase, options)?;
+    Ok(encrypted.to_armor()?)
+}
+
+/// Decrypt labels encrypted with a passphrase, from an ASCII armored or hex string,
+/// or binary age bytes.
+#[wasm_bindgen(js_name = decryptLabels, unchecked_return_type = "Label[]")]
+pub fn decrypt_labels(
+    #[wasm_bindgen(unchecked_param_type = "string | Uint8Array")] encrypted: JsValue,
+    passphrase: &str,
+) -> Result<JsValue, JsError> {
+    let encoded = match encrypted.as_string() {
+        Some(encrypted) => encrypted.into_bytes(),
+        None => encrypted
+            .dyn_into::<js_sys::Uint8Array>()
+            .map_err(|_| JsError::new("Encrypted labels must be a string or a Uint8Array"))?
+            .to_vec(),
+    };
+
+    let labels = EncryptedLabels::from_encoded(encoded)?.decrypt(passphrase)?;
+    to_js(&labels)
+}
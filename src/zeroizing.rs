@@ -0,0 +1,141 @@
+//! Zeroize support for labels, behind the `zeroize` feature.
+//!
+//! Label text and origins are cleared from memory when a [`Labels`] struct is
+//! dropped. Record refs are only cleared when they are strings: txids, outpoints and
+//! addresses are public on-chain data.
+
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{
+    AddressRecord, ExtendedPublicKeyRecord, InputRecord, Label, Labels, OutputRecord,
+    PublicKeyRecord, SilentPaymentsScanRecord, TransactionRecord,
+};
+
+impl Drop for Labels {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Labels {}
+
+impl Zeroize for Labels {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Zeroize for Label {
+    fn zeroize(&mut self) {
+        match self {
+            Label::Transaction(record) => record.zeroize(),
+            Label::Address(record) => record.zeroize(),
+            Label::PublicKey(record) => record.zeroize(),
+            Label::Input(record) => record.zeroize(),
+            Label::Output(record) => record.zeroize(),
+            Label::ExtendedPublicKey(record) => record.zeroize(),
+            Label::SilentPaymentsScan(record) => record.zeroize(),
+        }
+    }
+}
+
+impl Zeroize for TransactionRecord {
+    fn zeroize(&mut self) {
+        self.label.zeroize();
+        self.origin.zeroize();
+    }
+}
+
+impl Zeroize for AddressRecord {
+    fn zeroize(&mut self) {
+        self.label.zeroize();
+    }
+}
+
+impl Zeroize for PublicKeyRecord {
+    fn zeroize(&mut self) {
+        self.ref_.zeroize();
+        self.label.zeroize();
+    }
+}
+
+impl Zeroize for InputRecord {
+    fn zeroize(&mut self) {
+        self.label.zeroize();
+    }
+}
+
+impl Zeroize for OutputRecord {
+    fn zeroize(&mut self) {
+        self.label.zeroize();
+        self.spendable.zeroize();
+    }
+}
+
+impl Zeroize for ExtendedPublicKeyRecord {
+    fn zeroize(&mut self) {
+        self.ref_.zeroize();
+        self.label.zeroize();
+    }
+}
+
+impl Zeroize for SilentPaymentsScanRecord {
+    fn zeroize(&mut self) {
+        self.ref_.zeroize();
+        self.label.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The bytes of an allocation, which must still be alive
+    unsafe fn allocation<T>(ptr: *const T, capacity: usize) -> &'static [u8] {
+        std::slice::from_raw_parts(ptr.cast::<u8>(), capacity * std::mem::size_of::<T>())
+    }
+
+    #[test]
+    fn zeroize_clears_label_text() {
+        let mut labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let mut label = labels.first().cloned().unwrap();
+
+        label.zeroize();
+        assert_eq!(label.label(), None);
+
+        let records = (labels.as_ptr(), labels.capacity());
+        labels.zeroize();
+        assert!(labels.is_empty());
+
+        // the records, and the pointers to their strings, are wiped in place
+        let bytes = unsafe { allocation(records.0, records.1) };
+        assert!(bytes.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn zeroize_wipes_string_buffers() {
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let mut wiped = 0;
+
+        for mut label in labels.iter().cloned() {
+            let ref_ = match &label {
+                Label::PublicKey(record) => &record.ref_,
+                Label::ExtendedPublicKey(record) => &record.ref_,
+                Label::SilentPaymentsScan(record) => &record.ref_,
+                _ => continue,
+            };
+            let buffer = (ref_.as_ptr(), ref_.capacity());
+
+            label.zeroize();
+            assert_eq!(label.label(), None);
+            assert_eq!(label.ref_().to_string(), "");
+
+            // the ref is kept allocated, and every byte of it is wiped
+            let bytes = unsafe { allocation(buffer.0, buffer.1) };
+            assert!(bytes.iter().all(|&byte| byte == 0));
+            wiped += 1;
+        }
+
+        assert_eq!(wiped, 3);
+    }
+}
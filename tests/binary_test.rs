@@ -0,0 +1,23 @@
+#[cfg(feature = "binary")]
+mod test {
+    use bip329::Labels;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn loop_back_binary() {
+        let labels_1 = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let binary = labels_1.to_binary().unwrap();
+        let labels_2 = Labels::try_from_binary(&binary).unwrap();
+
+        assert_eq!(labels_1, labels_2);
+    }
+
+    #[test]
+    fn loop_back_binary_test_vector() {
+        let labels_1 = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+        let binary = labels_1.to_binary().unwrap();
+        let labels_2 = Labels::try_from_binary(&binary).unwrap();
+
+        assert_eq!(labels_1, labels_2);
+    }
+}
@@ -0,0 +1,36 @@
+// Run with `just test-kotlin`, from the repository root
+import uniffi.bip329.*
+
+val labels = Labels.tryFromFile("tests/data/labels.jsonl")
+check(labels.len() == 72uL)
+check(!labels.isEmpty())
+
+val records = labels.labels()
+val transaction = records.filterIsInstance<Label.Transaction>().first()
+check(labelRefToString(labelRef(transaction)) == transaction.v1.`ref`)
+
+check(Labels(records) == labels)
+check(Labels.tryFromStr(labels.export()) == labels)
+
+val unknown = labels.export() + "\n{\"type\":\"unknown\",\"ref\":\"unknown\"}"
+try {
+    Labels.tryFromStr(unknown)
+    throw AssertionError("unknown record types are rejected by default")
+} catch (error: ParseException.InvalidLine) {
+}
+
+val options = LabelParseOptions(ignoreUnknownTypes = true)
+check(Labels.tryFromStrWithOptions(unknown, options) == labels)
+
+val encrypted = EncryptedLabels.encrypt(labels, "passphrase")
+check(EncryptedLabels.fromArmor(encrypted.toArmor()).decrypt("passphrase") == labels)
+check(EncryptedLabels.fromEncoded(encrypted.toBytes()) == encrypted)
+
+try {
+    encrypted.decrypt("wrong passphrase")
+    throw AssertionError("decrypting with the wrong passphrase fails")
+} catch (error: EncryptionException.WrongPassphrase) {
+}
+
+val fixture = EncryptedLabels.readFromFile("tests/data/encrypted_labels.age")
+check(fixture.decrypt("passphrase") == labels)
@@ -0,0 +1,47 @@
+// Run with `just test-swift`, from the repository root, compiled together with the
+// generated bindings
+
+@main
+struct TestLabels {
+    static func main() throws {
+        let labels = try Labels.tryFromFile(path: "tests/data/labels.jsonl")
+        precondition(labels.len() == 72)
+        precondition(!labels.isEmpty())
+
+        let records = labels.labels()
+        guard case let .transaction(transaction) = records.first(where: {
+            if case .transaction = $0 { return true }
+            return false
+        }) else {
+            fatalError("labels include transactions")
+        }
+        precondition(labelRefToString(ref: labelRef(label: .transaction(transaction))) == transaction.ref)
+
+        precondition(Labels(labels: records) == labels)
+        precondition(try Labels.tryFromStr(labels: labels.export()) == labels)
+
+        let unknown = try labels.export() + "\n{\"type\":\"unknown\",\"ref\":\"unknown\"}"
+        do {
+            _ = try Labels.tryFromStr(labels: unknown)
+            fatalError("unknown record types are rejected by default")
+        } catch ParseError.InvalidLine {
+        }
+
+        let options = LabelParseOptions(ignoreUnknownTypes: true)
+        precondition(try Labels.tryFromStrWithOptions(labels: unknown, options: options) == labels)
+
+        let encrypted = try EncryptedLabels.encrypt(labels: labels, passphrase: "passphrase")
+        let armored = try EncryptedLabels.fromArmor(armor: encrypted.toArmor())
+        precondition(try armored.decrypt(passphrase: "passphrase") == labels)
+        precondition(try EncryptedLabels.fromEncoded(encoded: encrypted.toBytes()) == encrypted)
+
+        do {
+            _ = try encrypted.decrypt(passphrase: "wrong passphrase")
+            fatalError("decrypting with the wrong passphrase fails")
+        } catch EncryptionError.WrongPassphrase {
+        }
+
+        let fixture = try EncryptedLabels.readFromFile(path: "tests/data/encrypted_labels.age")
+        precondition(try fixture.decrypt(passphrase: "passphrase") == labels)
+    }
+}
@@ -0,0 +1,59 @@
+use bip329::Labels;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn canonical_export_matches_golden_file() {
+    let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+    let expected = std::fs::read_to_string("tests/data/canonical/labels.jsonl").unwrap();
+
+    assert_eq!(labels.export_canonical().unwrap(), expected);
+}
+
+#[test]
+fn canonical_export_test_vector_matches_golden_file() {
+    let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+    let expected = std::fs::read_to_string("tests/data/canonical/test_vector.jsonl").unwrap();
+
+    assert_eq!(labels.export_canonical().unwrap(), expected);
+}
+
+#[test]
+fn canonical_export_ignores_record_order_and_spendable_representation() {
+    let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+
+    let mut reversed = labels.clone();
+    reversed.reverse();
+
+    // `"spendable": "true"` and an omitted `spendable` both mean spendable
+    let spendable_string = r#"{"type": "output", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:1", "label": "Output", "spendable": "true"}"#;
+    let spendable_omitted = r#"{"type": "output", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd:1", "label": "Output"}"#;
+
+    assert_eq!(
+        labels.export_canonical().unwrap(),
+        reversed.export_canonical().unwrap()
+    );
+    assert_eq!(
+        Labels::try_from_str(spendable_string)
+            .unwrap()
+            .export_canonical()
+            .unwrap(),
+        Labels::try_from_str(spendable_omitted)
+            .unwrap()
+            .export_canonical()
+            .unwrap()
+    );
+}
+
+#[test]
+fn canonical_export_of_empty_labels_is_empty() {
+    assert_eq!(Labels::default().export_canonical().unwrap(), "");
+}
+
+#[test]
+fn canonical_export_escapes_only_required_characters() {
+    let jsonl = r#"{"type": "tx", "ref": "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd", "label": "café \"quoted\"\n\u0001/"}"#;
+    let labels = Labels::try_from_str(jsonl).unwrap();
+
+    let expected = "{\"label\":\"café \\\"quoted\\\"\\n\\u0001/\",\"ref\":\"f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd\",\"type\":\"tx\"}\n";
+    assert_eq!(labels.export_canonical().unwrap(), expected);
+}
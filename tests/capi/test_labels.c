@@ -0,0 +1,204 @@
+// Test harness for the C bindings, run from the crate root by tests/capi_test.rs or `just test-c`
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "bip329.h"
+
+// unlike assert, always evaluates the condition, which calls into the library
+#define CHECK(condition)                                                               \
+    do {                                                                               \
+        if (!(condition)) {                                                            \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+            exit(1);                                                                   \
+        }                                                                              \
+    } while (0)
+
+#define TX "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd"
+
+static char *read_file(const char *path, size_t *len) {
+    FILE *file = fopen(path, "rb");
+    CHECK(file);
+
+    fseek(file, 0, SEEK_END);
+    *len = (size_t)ftell(file);
+    rewind(file);
+
+    char *data = malloc(*len + 1);
+    CHECK(fread(data, 1, *len, file) == *len);
+    data[*len] = '\0';
+
+    fclose(file);
+    return data;
+}
+
+static void test_parse_test_vector(void) {
+    Bip329Labels *labels = NULL;
+    CHECK(bip329_labels_parse_file("tests/data/test_vector.jsonl", NULL, &labels) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(labels) == 8);
+
+    Bip329LabelType expected[] = {
+        BIP329_LABEL_TYPE_TRANSACTION, BIP329_LABEL_TYPE_ADDRESS,
+        BIP329_LABEL_TYPE_PUBLIC_KEY, BIP329_LABEL_TYPE_INPUT,
+        BIP329_LABEL_TYPE_OUTPUT, BIP329_LABEL_TYPE_EXTENDED_PUBLIC_KEY,
+        BIP329_LABEL_TYPE_SILENT_PAYMENTS_SCAN, BIP329_LABEL_TYPE_TRANSACTION,
+    };
+
+    for (size_t index = 0; index < bip329_labels_len(labels); index++) {
+        Bip329Record *record = NULL;
+        CHECK(bip329_labels_get(labels, index, &record) == BIP329_ERROR_CODE_OK);
+        CHECK(bip329_record_type(record) == expected[index]);
+        CHECK(bip329_record_ref(record) != NULL);
+        bip329_record_free(record);
+    }
+
+    Bip329Record *tx = NULL;
+    CHECK(bip329_labels_get(labels, 0, &tx) == BIP329_ERROR_CODE_OK);
+    CHECK(strcmp(bip329_record_ref(tx), TX) == 0);
+    CHECK(strcmp(bip329_record_label(tx), "Transaction") == 0);
+    CHECK(strcmp(bip329_record_origin(tx), "wpkh([d34db33f/84'/0'/0'])") == 0);
+    CHECK(bip329_record_spendable(tx) == -1);
+    bip329_record_free(tx);
+
+    Bip329Record *output = NULL;
+    CHECK(bip329_labels_get(labels, 4, &output) == BIP329_ERROR_CODE_OK);
+    CHECK(strcmp(bip329_record_ref(output), TX ":1") == 0);
+    CHECK(bip329_record_origin(output) == NULL);
+    CHECK(bip329_record_spendable(output) == 0);
+    bip329_record_free(output);
+
+    Bip329Record *missing = NULL;
+    CHECK(bip329_labels_get(labels, 8, &missing) == BIP329_ERROR_CODE_INDEX_OUT_OF_RANGE);
+    CHECK(missing == NULL);
+
+    bip329_labels_free(labels);
+}
+
+static void test_export_round_trip(void) {
+    size_t len = 0;
+    char *jsonl = read_file("tests/data/labels.jsonl", &len);
+
+    Bip329Labels *labels = NULL;
+    CHECK(bip329_labels_parse(jsonl, NULL, &labels) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(labels) == 72);
+    free(jsonl);
+
+    char *exported = NULL;
+    CHECK(bip329_labels_export(labels, &exported) == BIP329_ERROR_CODE_OK);
+
+    Bip329Labels *reparsed = NULL;
+    CHECK(bip329_labels_parse(exported, NULL, &reparsed) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(reparsed) == 72);
+
+    char *reexported = NULL;
+    CHECK(bip329_labels_export(reparsed, &reexported) == BIP329_ERROR_CODE_OK);
+    CHECK(strcmp(exported, reexported) == 0);
+
+    bip329_string_free(exported);
+    bip329_string_free(reexported);
+    bip329_labels_free(labels);
+    bip329_labels_free(reparsed);
+}
+
+static void test_errors(void) {
+    Bip329Labels *labels = NULL;
+    const char *jsonl = "{\"type\": \"tx\", \"ref\": \"" TX "\"}\nnot json";
+    CHECK(bip329_labels_parse(jsonl, NULL, &labels) == BIP329_ERROR_CODE_PARSE_ERROR);
+    CHECK(labels == NULL);
+    CHECK(strstr(bip329_last_error_message(), "line 2") != NULL);
+
+    const char *unknown = "{\"type\": \"tx\", \"ref\": \"" TX "\"}\n{\"type\": \"future\", \"ref\": \"x\"}";
+    CHECK(bip329_labels_parse(unknown, NULL, &labels) == BIP329_ERROR_CODE_PARSE_ERROR);
+
+    Bip329ParseOptions options = {.ignore_unknown_types = true};
+    CHECK(bip329_labels_parse(unknown, &options, &labels) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(labels) == 1);
+    CHECK(bip329_labels_export(labels, NULL) == BIP329_ERROR_CODE_NULL_POINTER);
+    CHECK(bip329_labels_get(labels, 0, NULL) == BIP329_ERROR_CODE_NULL_POINTER);
+    bip329_labels_free(labels);
+    CHECK(bip329_labels_parse(unknown, &options, NULL) == BIP329_ERROR_CODE_NULL_POINTER);
+
+    CHECK(bip329_labels_parse_file("tests/data/missing.jsonl", NULL, &labels) == BIP329_ERROR_CODE_FILE_READ_ERROR);
+    CHECK(bip329_labels_parse(NULL, NULL, &labels) == BIP329_ERROR_CODE_NULL_POINTER);
+    CHECK(bip329_labels_parse("\xff", NULL, &labels) == BIP329_ERROR_CODE_INVALID_STRING);
+    CHECK(bip329_labels_len(NULL) == 0);
+
+    CHECK(bip329_record_type(NULL) == BIP329_LABEL_TYPE_INVALID);
+    CHECK(bip329_record_ref(NULL) == NULL);
+    CHECK(bip329_record_label(NULL) == NULL);
+    CHECK(bip329_record_origin(NULL) == NULL);
+    CHECK(bip329_record_spendable(NULL) == -1);
+
+    bip329_labels_free(NULL);
+    bip329_record_free(NULL);
+    bip329_string_free(NULL);
+}
+
+static void test_decrypt_test_data(void) {
+    Bip329Labels *labels = NULL;
+    CHECK(bip329_labels_decrypt_file("tests/data/encrypted_labels.age", "passphrase", &labels) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(labels) == 72);
+
+    Bip329Record *xpub = NULL;
+    CHECK(bip329_labels_get(labels, 0, &xpub) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_record_type(xpub) == BIP329_LABEL_TYPE_EXTENDED_PUBLIC_KEY);
+    CHECK(strcmp(bip329_record_label(xpub), "BIP39") == 0);
+    bip329_record_free(xpub);
+    bip329_labels_free(labels);
+
+    size_t len = 0;
+    char *armor = read_file("tests/data/encrypted_labels.age.asc", &len);
+    CHECK(bip329_labels_decrypt((const uint8_t *)armor, len, "passphrase", &labels) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(labels) == 72);
+    bip329_labels_free(labels);
+
+    labels = NULL;
+    CHECK(bip329_labels_decrypt((const uint8_t *)armor, len, "wrong passphrase", &labels) == BIP329_ERROR_CODE_WRONG_PASSPHRASE);
+    CHECK(labels == NULL);
+    free(armor);
+}
+
+static void test_encrypt_round_trip(const char *dir) {
+    Bip329Labels *labels = NULL;
+    CHECK(bip329_labels_parse_file("tests/data/test_vector.jsonl", NULL, &labels) == BIP329_ERROR_CODE_OK);
+
+    char *armor = NULL;
+    CHECK(bip329_labels_encrypt(labels, "passphrase", &armor) == BIP329_ERROR_CODE_OK);
+    CHECK(strncmp(armor, "-----BEGIN AGE ENCRYPTED FILE-----", 34) == 0);
+
+    Bip329Labels *decrypted = NULL;
+    CHECK(bip329_labels_decrypt((const uint8_t *)armor, strlen(armor), "passphrase", &decrypted) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(decrypted) == 8);
+    bip329_labels_free(decrypted);
+    bip329_string_free(armor);
+
+    char path[4096];
+    snprintf(path, sizeof(path), "%s/labels.age", dir);
+    CHECK(bip329_labels_encrypt_to_file(labels, "passphrase", path) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_decrypt_file(path, "passphrase", &decrypted) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(decrypted) == 8);
+    bip329_labels_free(decrypted);
+
+    snprintf(path, sizeof(path), "%s/labels.jsonl", dir);
+    CHECK(bip329_labels_export_to_file(labels, path) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_parse_file(path, NULL, &decrypted) == BIP329_ERROR_CODE_OK);
+    CHECK(bip329_labels_len(decrypted) == 8);
+    bip329_labels_free(decrypted);
+
+    bip329_labels_free(labels);
+}
+
+int main(int argc, char **argv) {
+    // the directory for files written by the tests
+    const char *dir = argc > 1 ? argv[1] : ".";
+
+    test_parse_test_vector();
+    test_export_round_trip();
+    test_errors();
+    test_decrypt_test_data();
+    test_encrypt_round_trip(dir);
+
+    printf("all C API tests passed\n");
+    return 0;
+}
@@ -0,0 +1,40 @@
+#[cfg(feature = "capi")]
+mod common;
+
+#[cfg(feature = "capi")]
+mod test {
+    use std::process::Command;
+
+    use crate::common::build_cdylib;
+
+    #[test]
+    fn c_harness_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let harness = dir.path().join("test_labels");
+        let library = build_cdylib("capi");
+        let library_dir = library.parent().unwrap();
+
+        let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let status = Command::new(compiler)
+            .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-Iinclude"])
+            .arg("tests/capi/test_labels.c")
+            .arg("-L")
+            .arg(library_dir)
+            .args(["-lbip329", "-o"])
+            .arg(&harness)
+            .status()
+            .unwrap();
+
+        assert!(status.success(), "failed to compile the C harness");
+
+        let output = Command::new(&harness)
+            .arg(dir.path())
+            .env("LD_LIBRARY_PATH", library_dir)
+            .env("DYLD_LIBRARY_PATH", library_dir)
+            .output()
+            .unwrap();
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "C harness failed: {stderr}");
+    }
+}
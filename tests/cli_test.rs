@@ -0,0 +1,137 @@
+#[cfg(feature = "cli")]
+mod test {
+    use std::{
+        path::Path,
+        process::{Command, Output},
+    };
+
+    use bip329::Labels;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    const LABELS: &str = "tests/data/labels.jsonl";
+
+    fn bip329(args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_bip329"))
+            .args(args)
+            .env("BIP329_PASSPHRASE", "passphrase")
+            .output()
+            .unwrap()
+    }
+
+    fn json(output: &Output) -> Value {
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+
+    fn path(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn validate_reports_invalid_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let invalid = dir.path().join("invalid.jsonl");
+        let contents = std::fs::read_to_string(LABELS).unwrap();
+        let mut lines = contents.lines().take(2).collect::<Vec<_>>();
+        lines.push(r#"{"type":"tx","ref":"not a txid"}"#);
+        std::fs::write(&invalid, lines.join("\n")).unwrap();
+
+        let output = bip329(&["--json", "validate", LABELS]);
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(json(&output)["files"][0]["records"], 72);
+
+        let output = bip329(&["--json", "validate", LABELS, path(&invalid)]);
+        assert_eq!(output.status.code(), Some(1));
+
+        let report = json(&output);
+        assert_eq!(report["valid"], false);
+        assert_eq!(report["files"][1]["error"]["line"], 3);
+    }
+
+    #[test]
+    fn convert_csv_loop_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("labels.csv");
+        let jsonl = dir.path().join("labels.jsonl");
+
+        let output = bip329(&["convert", LABELS, "--to", "csv", "-o", path(&csv)]);
+        assert!(output.status.success());
+
+        let output = bip329(&["convert", path(&csv), "--to", "jsonl", "-o", path(&jsonl)]);
+        assert!(output.status.success());
+
+        assert_eq!(
+            Labels::try_from_file(&jsonl).unwrap(),
+            Labels::try_from_file(LABELS).unwrap()
+        );
+    }
+
+    #[test]
+    fn convert_reports_skipped_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let electrum = dir.path().join("labels.json");
+
+        let output = bip329(&["--json", "convert", LABELS, "--to", "electrum"]);
+        assert!(output.status.success());
+        let summary: Value = serde_json::from_slice(&output.stderr).unwrap();
+        let skipped = summary["skipped"].as_u64().unwrap();
+        assert!(skipped > 0);
+
+        let stdout: Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(summary["records"], stdout.as_object().unwrap().len());
+
+        let args = ["--json", "convert", LABELS, "--to", "electrum", "-o"];
+        let output = bip329(&[&args[..], &[path(&electrum)]].concat());
+        assert!(output.status.success());
+        assert!(output.stderr.is_empty());
+        assert_eq!(json(&output)["skipped"], skipped);
+    }
+
+    #[test]
+    fn merge_diff_and_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let transactions = dir.path().join("tx.jsonl");
+        let merged = dir.path().join("merged.jsonl");
+
+        let output = bip329(&["filter", LABELS, "--type", "tx", "-o", path(&transactions)]);
+        assert!(output.status.success());
+        assert_eq!(Labels::try_from_file(&transactions).unwrap().len(), 21);
+
+        let output = bip329(&["diff", LABELS, path(&transactions)]);
+        assert_eq!(output.status.code(), Some(1));
+
+        let output = bip329(&["merge", path(&transactions), LABELS, "-o", path(&merged)]);
+        assert!(output.status.success());
+
+        let output = bip329(&["--json", "diff", LABELS, path(&merged)]);
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(json(&output)["identical"], true);
+
+        let output = bip329(&["filter", LABELS, "--type", "transaction"]);
+        assert_eq!(output.status.code(), Some(4));
+    }
+
+    #[test]
+    fn encrypt_decrypt_loop_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let encrypted = dir.path().join("labels.age");
+        let decrypted = dir.path().join("labels.jsonl");
+        let wrong = dir.path().join("wrong");
+        std::fs::write(&wrong, "wrong passphrase\n").unwrap();
+
+        let output = bip329(&["encrypt", LABELS, "-o", path(&encrypted)]);
+        assert!(output.status.success());
+
+        let output = bip329(&["decrypt", path(&encrypted), "-o", path(&decrypted)]);
+        assert!(output.status.success());
+        assert_eq!(
+            Labels::try_from_file(&decrypted).unwrap(),
+            Labels::try_from_file(LABELS).unwrap()
+        );
+
+        let args = ["--json", "decrypt", path(&encrypted), "--passphrase-file"];
+        let output = bip329(&[&args[..], &[path(&wrong)]].concat());
+        assert_eq!(output.status.code(), Some(5));
+        assert_eq!(json(&output)["error"]["kind"], "wrong_passphrase");
+    }
+}
@@ -0,0 +1,31 @@
+//! Helpers shared by the integration tests
+
+use std::{
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Build the crate as a C dynamic library with the features, and return its path.
+///
+/// The crate only builds as a Rust library, so foreign language tests build the
+/// dynamic library themselves, into a target directory for the features, so test
+/// binaries with other features don't replace it while it is in use.
+pub fn build_cdylib(features: &str) -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("cdylib")
+        .join(features.replace(',', "-"));
+
+    let status = Command::new(env!("CARGO"))
+        .args(["rustc", "--quiet", "--lib", "--crate-type", "cdylib"])
+        .args(["--features", features])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .unwrap();
+
+    assert!(status.success(), "failed to build the dynamic library");
+
+    let library = format!("{DLL_PREFIX}bip329{DLL_SUFFIX}");
+    target_dir.join("debug").join(library)
+}
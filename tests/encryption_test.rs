@@ -11,6 +11,16 @@ mod test {
         assert_eq!(labels, decrypted);
     }
 
+    #[test]
+    fn test_armored_decryption() {
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+        let encrypted =
+            EncryptedLabels::read_from_file("tests/data/encrypted_labels.age.asc").unwrap();
+        let decrypted = encrypted.decrypt("passphrase").unwrap();
+
+        assert_eq!(labels, decrypted);
+    }
+
     #[test]
     fn test_loop_back_encryption() {
         use pretty_assertions::assert_eq;
@@ -21,4 +31,62 @@ mod test {
 
         assert_eq!(labels_1, decrypted);
     }
+
+    #[test]
+    fn test_recipient_encryption() {
+        use bip329::encryption::{
+            age::{self, secrecy::ExposeSecret as _},
+            parse_identities, parse_recipient,
+        };
+
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let phone = age::x25519::Identity::generate();
+        let laptop = age::x25519::Identity::generate();
+        let other = age::x25519::Identity::generate();
+
+        let recipients = [&phone, &laptop]
+            .map(|identity| parse_recipient(&identity.to_public().to_string()).unwrap());
+        let encrypted = EncryptedLabels::encrypt_to_recipients(&labels, &recipients).unwrap();
+
+        for identity in [&phone, &laptop] {
+            let identity_file = format!(
+                "# created: 2026-10-18T00:00:00Z\n{}\n",
+                identity.to_string().expose_secret()
+            );
+            let identities = parse_identities(&identity_file).unwrap();
+            let decrypted = encrypted.decrypt_with_identities(&identities).unwrap();
+
+            assert_eq!(labels, decrypted);
+        }
+
+        let identities = parse_identities(other.to_string().expose_secret()).unwrap();
+        assert!(encrypted.decrypt_with_identities(&identities).is_err());
+        assert!(encrypted.decrypt("passphrase").is_err());
+    }
+
+    #[test]
+    fn test_invalid_recipient_and_identity() {
+        use bip329::encryption::{parse_identities, parse_recipient};
+
+        assert!(parse_recipient("age1notarecipient").is_err());
+        assert!(parse_identities("AGE-SECRET-KEY-1NOTAKEY").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "ssh")]
+    fn test_ssh_recipient_encryption() {
+        use bip329::encryption::{parse_recipient, read_identities_file};
+
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let public_key = std::fs::read_to_string("tests/data/keys/ssh_ed25519.pub").unwrap();
+        let recipients = [parse_recipient(&public_key).unwrap()];
+        let encrypted = EncryptedLabels::encrypt_to_recipients(&labels, &recipients).unwrap();
+
+        let identities = read_identities_file("tests/data/keys/ssh_ed25519").unwrap();
+        let decrypted = encrypted.decrypt_with_identities(&identities).unwrap();
+
+        assert_eq!(labels, decrypted);
+    }
 }
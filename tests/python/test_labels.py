@@ -0,0 +1,135 @@
+from pathlib import Path
+
+import pytest
+
+import bip329
+from bip329 import EncryptedLabels, Label, LabelParseOptions, Labels
+
+DATA = Path(__file__).parent.parent / "data"
+TX = "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd"
+
+
+def test_parse_test_vector():
+    labels = Labels.try_from_file(DATA / "test_vector.jsonl")
+
+    assert len(labels) == 8
+    assert [label.type for label in labels] == [
+        "tx",
+        "addr",
+        "pubkey",
+        "input",
+        "output",
+        "xpub",
+        "spscan",
+        "tx",
+    ]
+
+    tx = labels[0]
+    assert tx.ref == TX
+    assert tx.label == "Transaction"
+    assert tx.origin == "wpkh([d34db33f/84'/0'/0'])"
+    assert tx.spendable is None
+
+    output = labels[4]
+    assert output.ref == f"{TX}:1"
+    assert output.spendable is False
+    assert labels[-1].label == "Account #1 Transaction"
+
+
+def test_round_trip():
+    jsonl = (DATA / "labels.jsonl").read_text()
+    labels = Labels.try_from_str(jsonl)
+
+    assert len(labels) == 72
+    assert Labels.try_from_str(labels.export()) == labels
+
+
+def test_export_to_file(tmp_path):
+    labels = Labels.try_from_file(DATA / "test_vector.jsonl")
+    path = tmp_path / "labels.jsonl"
+
+    labels.export_to_file(path)
+    assert Labels.try_from_file(str(path)) == labels
+
+
+def test_dicts():
+    labels = Labels.try_from_file(DATA / "test_vector.jsonl")
+    dicts = labels.to_dicts()
+
+    assert dicts[0] == {
+        "type": "tx",
+        "ref": TX,
+        "label": "Transaction",
+        "origin": "wpkh([d34db33f/84'/0'/0'])",
+    }
+    assert dicts[4]["spendable"] is False
+    assert Labels(dicts) == labels
+    assert Label.from_dict(dicts[1]) == labels[1]
+
+
+def test_construct_from_labels_and_dicts():
+    label = Label.from_dict({"type": "tx", "ref": TX, "label": "Rent"})
+    labels = Labels([label, {"type": "output", "ref": f"{TX}:0", "spendable": True}])
+
+    assert len(labels) == 2
+    assert labels[0] == label
+    assert labels[1].label is None
+    assert labels[1].spendable is True
+    assert len({label, labels[0]}) == 1
+    assert len(Labels()) == 0
+
+
+def test_invalid_labels():
+    with pytest.raises(bip329.ParseError, match="line 2"):
+        Labels.try_from_str(f'{{"type": "tx", "ref": "{TX}"}}\nnot json')
+
+    with pytest.raises(bip329.ParseError):
+        Label.from_dict({"type": "tx", "ref": "not a txid"})
+
+    with pytest.raises(TypeError):
+        Labels([1])
+
+    with pytest.raises(IndexError):
+        Labels()[0]
+
+    with pytest.raises(OSError):
+        Labels.try_from_file(DATA / "missing.jsonl")
+
+
+def test_ignore_unknown_types():
+    jsonl = f'{{"type": "tx", "ref": "{TX}"}}\n{{"type": "future", "ref": "x"}}'
+
+    with pytest.raises(bip329.ParseError):
+        Labels.try_from_str(jsonl)
+
+    options = LabelParseOptions(ignore_unknown_types=True)
+    assert options.ignore_unknown_types
+    assert len(Labels.try_from_str(jsonl, options)) == 1
+
+
+def test_decrypt_test_data():
+    encrypted = EncryptedLabels.read_from_file(DATA / "encrypted_labels.age")
+    labels = encrypted.decrypt("passphrase")
+
+    assert labels == Labels.try_from_file(DATA / "labels.jsonl")
+    assert labels[0].type == "xpub"
+    assert labels[0].label == "BIP39"
+
+    armored = EncryptedLabels.read_from_file(DATA / "encrypted_labels.age.asc")
+    assert armored.decrypt("passphrase") == labels
+
+    with pytest.raises(bip329.WrongPassphraseError):
+        encrypted.decrypt("wrong passphrase")
+
+
+def test_encrypt_round_trip(tmp_path):
+    labels = Labels.try_from_file(DATA / "test_vector.jsonl")
+    encrypted = EncryptedLabels.encrypt(labels, "passphrase", work_factor=10)
+
+    assert EncryptedLabels.from_hex(encrypted.to_hex()).decrypt("passphrase") == labels
+    assert EncryptedLabels.from_armor(encrypted.to_armor()).decrypt("passphrase") == labels
+    assert EncryptedLabels.from_bytes(bytes(encrypted)).decrypt("passphrase") == labels
+
+    path = tmp_path / "labels.age"
+    encrypted.write_to_file(path)
+    assert EncryptedLabels.read_from_file(path).decrypt("passphrase") == labels
@@ -0,0 +1,250 @@
+#[cfg(all(feature = "server", feature = "client"))]
+mod test {
+    use bip329::{
+        encryption::{EncryptedLabels, PassphraseOptions},
+        error::ClientError,
+        remote::{
+            client::LabelClient,
+            server::{LabelServer, ServerHandle},
+        },
+        Labels,
+    };
+    use pretty_assertions::assert_eq;
+
+    const WALLET: &str = "wallet-1";
+
+    fn start(dir: &tempfile::TempDir) -> ServerHandle {
+        LabelServer::bind("127.0.0.1:0", dir.path())
+            .unwrap()
+            .spawn()
+    }
+
+    fn encrypt(labels: &Labels) -> EncryptedLabels {
+        let options = PassphraseOptions::default().work_factor(10);
+        EncryptedLabels::encrypt_with_options(labels, "passphrase", options).unwrap()
+    }
+
+    fn test_vector() -> Labels {
+        Labels::try_from_file("tests/data/test_vector.jsonl").unwrap()
+    }
+
+    #[test]
+    fn upload_and_download_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = start(&dir);
+        let client = LabelClient::new(server.url());
+
+        assert_eq!(client.download(WALLET).unwrap(), None);
+        assert_eq!(client.versions(WALLET).unwrap(), Vec::<u64>::new());
+
+        let first = test_vector();
+        let mut second = first.clone();
+        second.remove(0);
+
+        assert_eq!(client.upload(WALLET, &encrypt(&first), None).unwrap(), 1);
+        assert_eq!(
+            client.upload(WALLET, &encrypt(&second), Some(1)).unwrap(),
+            2
+        );
+
+        let latest = client.download(WALLET).unwrap().unwrap();
+        assert_eq!(latest.version, 2);
+        assert_eq!(latest.labels.decrypt("passphrase").unwrap(), second);
+
+        let old = client.download_version(WALLET, 1).unwrap().unwrap();
+        assert_eq!(old.version, 1);
+        assert_eq!(old.labels.decrypt("passphrase").unwrap(), first);
+
+        assert_eq!(client.versions(WALLET).unwrap(), vec![1, 2]);
+        assert_eq!(client.download_version(WALLET, 3).unwrap(), None);
+        assert_eq!(client.download("wallet-2").unwrap(), None);
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn stale_uploads_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = start(&dir);
+        let phone = LabelClient::new(server.url());
+        let laptop = LabelClient::new(format!("{}/", server.url()));
+
+        let labels = encrypt(&test_vector());
+        assert_eq!(phone.upload(WALLET, &labels, None).unwrap(), 1);
+
+        let error = laptop.upload(WALLET, &labels, None).unwrap_err();
+        assert!(matches!(
+            error,
+            ClientError::VersionConflict { current: Some(1) }
+        ));
+
+        assert_eq!(phone.upload(WALLET, &labels, Some(1)).unwrap(), 2);
+
+        let error = laptop.upload(WALLET, &labels, Some(1)).unwrap_err();
+        assert!(matches!(
+            error,
+            ClientError::VersionConflict { current: Some(2) }
+        ));
+
+        let error = laptop.upload("wallet-2", &labels, Some(1)).unwrap_err();
+        assert!(matches!(
+            error,
+            ClientError::VersionConflict { current: None }
+        ));
+
+        let latest = laptop.download(WALLET).unwrap().unwrap();
+        assert_eq!(
+            laptop
+                .upload(WALLET, &latest.labels, Some(latest.version))
+                .unwrap(),
+            3
+        );
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn invalid_requests_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = start(&dir);
+        let client = LabelClient::new(server.url());
+
+        let not_age = EncryptedLabels::from_hex("00ff").unwrap();
+        let error = client.upload(WALLET, &not_age, None).unwrap_err();
+        assert!(matches!(
+            error,
+            ClientError::UnexpectedResponse { status: 400, .. }
+        ));
+
+        let labels = encrypt(&test_vector());
+        let error = client.upload("../wallet", &labels, None).unwrap_err();
+        assert!(matches!(error, ClientError::InvalidWalletId(_)));
+
+        // uploads without a precondition could overwrite another device's labels
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .new_agent();
+
+        let url = format!("{}/wallets/{WALLET}", server.url());
+        let response = agent.put(&url).send(labels.as_bytes()).unwrap();
+        assert_eq!(response.status().as_u16(), 428);
+
+        let response = agent.delete(&url).call().unwrap();
+        assert_eq!(response.status().as_u16(), 405);
+
+        let url = format!("{}/wallets/not.valid", server.url());
+        let response = agent.get(&url).call().unwrap();
+        assert_eq!(response.status().as_u16(), 400);
+
+        assert_eq!(client.versions(WALLET).unwrap(), Vec::<u64>::new());
+        server.shutdown();
+    }
+
+    #[test]
+    fn if_match_any_requires_existing_labels() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = start(&dir);
+        let client = LabelClient::new(server.url());
+        let labels = encrypt(&test_vector());
+
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .new_agent();
+
+        let url = format!("{}/wallets/{WALLET}", server.url());
+        let upload = || {
+            let request = agent.put(&url).header("If-Match", "*");
+            request.send(labels.as_bytes()).unwrap()
+        };
+
+        assert_eq!(upload().status().as_u16(), 412);
+        assert_eq!(client.upload(WALLET, &labels, None).unwrap(), 1);
+
+        let response = upload();
+        assert_eq!(response.status().as_u16(), 201);
+        assert_eq!(response.headers().get("ETag").unwrap(), "\"2\"");
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn access_token_is_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = LabelServer::bind("127.0.0.1:0", dir.path())
+            .unwrap()
+            .with_token("secret")
+            .spawn();
+
+        let labels = encrypt(&test_vector());
+
+        let error = LabelClient::new(server.url()).download(WALLET).unwrap_err();
+        assert!(matches!(error, ClientError::Unauthorized));
+
+        let wrong = LabelClient::new(server.url()).with_token("wrong");
+        let error = wrong.upload(WALLET, &labels, None).unwrap_err();
+        assert!(matches!(error, ClientError::Unauthorized));
+
+        let client = LabelClient::new(server.url()).with_token("secret");
+        assert_eq!(client.upload(WALLET, &labels, None).unwrap(), 1);
+        assert_eq!(client.versions(WALLET).unwrap(), vec![1]);
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn labels_are_kept_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let labels = test_vector();
+
+        let server = start(&dir);
+        let client = LabelClient::new(server.url());
+        client.upload(WALLET, &encrypt(&labels), None).unwrap();
+        server.shutdown();
+
+        let server = start(&dir);
+        let client = LabelClient::new(server.url());
+        let remote = client.download(WALLET).unwrap().unwrap();
+        assert_eq!(remote.version, 1);
+        assert_eq!(remote.labels.decrypt("passphrase").unwrap(), labels);
+
+        server.shutdown();
+    }
+
+    #[cfg(feature = "server-bin")]
+    #[test]
+    fn server_binary_serves_labels() {
+        use std::{
+            io::{BufRead as _, BufReader},
+            process::{Command, Stdio},
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bip329-server"))
+            .args(["--listen", "127.0.0.1:0", "--data-dir"])
+            .arg(dir.path())
+            .env("BIP329_SERVER_TOKEN", "secret")
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // the server prints the address it is listening on once it is ready
+        let mut line = String::new();
+        BufReader::new(child.stderr.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let url = line.split_whitespace().last().unwrap().to_string();
+
+        let client = LabelClient::new(url).with_token("secret");
+        let labels = encrypt(&test_vector());
+        let result = client.upload(WALLET, &labels, None);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert_eq!(result.unwrap(), 1);
+        assert!(dir.path().join(WALLET).join("1.age").exists());
+    }
+}
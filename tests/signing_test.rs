@@ -0,0 +1,121 @@
+#[cfg(feature = "signing")]
+mod test {
+    use std::str::FromStr as _;
+
+    use bip329::{error::SignatureError, signing::LabelSignature, Label, Labels};
+    use bitcoin::{
+        bip32::{Xpriv, Xpub},
+        secp256k1::Secp256k1,
+        Address, CompressedPublicKey, KnownHrp, Network, PrivateKey,
+    };
+    use pretty_assertions::assert_eq;
+
+    // BIP32 test vector 1 master key
+    const XPRV: &str = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+
+    fn tampered(labels: &Labels) -> Labels {
+        let mut labels = labels.clone();
+        for label in labels.iter_mut() {
+            if let Label::Transaction(record) = label {
+                record.label = Some("Tampered".to_string());
+            }
+        }
+        labels
+    }
+
+    #[test]
+    fn bip32_signature_loop_back() {
+        let secp = Secp256k1::new();
+        let xprv = Xpriv::from_str(XPRV).unwrap();
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let signature = labels.sign(&xprv).unwrap();
+        assert_eq!(signature.fingerprint(), Some(xprv.fingerprint(&secp)));
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        signature.write_to_file(file.path()).unwrap();
+        let signature = LabelSignature::read_from_file(file.path()).unwrap();
+
+        // reordering records does not change the canonical export
+        let mut reversed = labels.clone();
+        reversed.reverse();
+
+        labels.verify_signature(&signature).unwrap();
+        reversed.verify_signature(&signature).unwrap();
+        signature
+            .verify_signer(&labels, &Xpub::from_priv(&secp, &xprv))
+            .unwrap();
+
+        assert!(matches!(
+            tampered(&labels).verify_signature(&signature),
+            Err(SignatureError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn bip32_signature_rejects_other_signer() {
+        let secp = Secp256k1::new();
+        let xprv = Xpriv::from_str(XPRV).unwrap();
+        let other = Xpriv::new_master(Network::Bitcoin, &[7; 32]).unwrap();
+        let labels = Labels::try_from_file("tests/data/test_vector.jsonl").unwrap();
+
+        let signature = labels.sign(&xprv).unwrap();
+
+        assert!(matches!(
+            signature.verify_signer(&labels, &Xpub::from_priv(&secp, &other)),
+            Err(SignatureError::SignerMismatch)
+        ));
+    }
+
+    #[test]
+    fn bip322_signature_loop_back() {
+        let secp = Secp256k1::new();
+        let xprv = Xpriv::from_str(XPRV).unwrap();
+        let private_key = PrivateKey::new(xprv.private_key, Network::Bitcoin);
+        let public_key = CompressedPublicKey::from_private_key(&secp, &private_key).unwrap();
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let (internal_key, _) = public_key.0.x_only_public_key();
+        let addresses = [
+            Address::p2wpkh(&public_key, KnownHrp::Mainnet),
+            Address::p2tr(&secp, internal_key, None, KnownHrp::Mainnet),
+        ];
+
+        for address in addresses {
+            let fingerprint = Some(xprv.fingerprint(&secp));
+            let signature =
+                LabelSignature::sign_with_address(&labels, &private_key, &address, fingerprint)
+                    .unwrap();
+
+            let exported = signature.export().unwrap();
+            let signature = LabelSignature::try_from_str(&exported).unwrap();
+
+            assert_eq!(signature.address(), Some(address.as_unchecked()));
+            labels.verify_signature(&signature).unwrap();
+            assert!(tampered(&labels).verify_signature(&signature).is_err());
+        }
+    }
+
+    #[test]
+    fn bip322_rejects_unsupported_and_mismatched_addresses() {
+        let secp = Secp256k1::new();
+        let xprv = Xpriv::from_str(XPRV).unwrap();
+        let private_key = PrivateKey::new(xprv.private_key, Network::Bitcoin);
+        let public_key = CompressedPublicKey::from_private_key(&secp, &private_key).unwrap();
+        let labels = Labels::try_from_file("tests/data/labels.jsonl").unwrap();
+
+        let p2pkh = Address::p2pkh(public_key, Network::Bitcoin);
+        assert!(matches!(
+            LabelSignature::sign_with_address(&labels, &private_key, &p2pkh, None),
+            Err(SignatureError::UnsupportedAddress)
+        ));
+
+        let other = Address::from_str("bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l")
+            .unwrap()
+            .assume_checked();
+        assert!(matches!(
+            LabelSignature::sign_with_address(&labels, &private_key, &other, None),
+            Err(SignatureError::SignerMismatch)
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(feature = "uniffi")]
+mod common;
+
+#[cfg(feature = "uniffi")]
+mod test {
+    use std::{path::Path, process::Command};
+
+    use crate::common::build_cdylib;
+
+    #[cfg(not(feature = "encryption"))]
+    const FEATURES: &str = "uniffi";
+    #[cfg(feature = "encryption")]
+    const FEATURES: &str = "uniffi,encryption";
+
+    fn generate(language: &str, out_dir: &Path) {
+        let status = Command::new(env!("CARGO_BIN_EXE_uniffi-bindgen"))
+            .args(["generate", "--no-format", "--library"])
+            .arg(build_cdylib(FEATURES))
+            .args(["--language", language, "--out-dir"])
+            .arg(out_dir)
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+    }
+
+    fn contains(haystack: &[u8], needle: &str) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle.as_bytes())
+    }
+
+    // the scaffolding functions and the metadata the bindings are generated from, the
+    // generated Kotlin and Swift are tested by `just test-kotlin` and `just test-swift`
+    #[test]
+    fn library_exports_the_api() {
+        let library = std::fs::read(build_cdylib(FEATURES)).unwrap();
+
+        for symbol in [
+            "uniffi_bip329_fn_constructor_labels_new",
+            "uniffi_bip329_fn_constructor_labels_try_from_str_with_options",
+            "uniffi_bip329_fn_constructor_labels_try_from_file",
+            "uniffi_bip329_fn_method_labels_export",
+            "uniffi_bip329_fn_method_labels_labels",
+            "uniffi_bip329_fn_func_label_ref",
+            "uniffi_bip329_fn_func_label_ref_to_string",
+            "UNIFFI_META_BIP329_INTERFACE_LABELS",
+            "UNIFFI_META_BIP329_ENUM_LABEL",
+            "UNIFFI_META_BIP329_ENUM_LABELREF",
+            "UNIFFI_META_BIP329_RECORD_OUTPUTRECORD",
+            "UNIFFI_META_BIP329_RECORD_LABELPARSEOPTIONS",
+            "UNIFFI_META_BIP329_ERROR_PARSEERROR",
+            "UNIFFI_META_BIP329_CUSTOM_TYPE_TXID",
+        ] {
+            assert!(contains(&library, symbol), "missing {symbol}");
+        }
+
+        #[cfg(feature = "encryption")]
+        for symbol in [
+            "uniffi_bip329_fn_constructor_encryptedlabels_encrypt",
+            "uniffi_bip329_fn_method_encryptedlabels_decrypt",
+            "UNIFFI_META_BIP329_INTERFACE_ENCRYPTEDLABELS",
+        ] {
+            assert!(contains(&library, symbol), "missing {symbol}");
+        }
+    }
+
+    #[test]
+    fn bindings_are_generated() {
+        let dir = tempfile::tempdir().unwrap();
+        generate("kotlin", dir.path());
+        generate("swift", dir.path());
+
+        for file in [
+            "uniffi/bip329/bip329.kt",
+            "bip329.swift",
+            "bip329FFI.h",
+            "bip329FFI.modulemap",
+        ] {
+            assert!(dir.path().join(file).exists(), "missing {file}");
+        }
+    }
+}
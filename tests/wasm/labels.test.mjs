@@ -0,0 +1,83 @@
+// Run with `just test-wasm`, which builds the Node package into target/wasm
+import assert from "node:assert/strict";
+import { readFileSync } from "node:fs";
+import { createRequire } from "node:module";
+import { test } from "node:test";
+
+const require = createRequire(import.meta.url);
+const bip329 = require(process.env.BIP329_WASM ?? "../../target/wasm/bip329.js");
+
+const data = (name) => new URL(`../data/${name}`, import.meta.url);
+const jsonl = readFileSync(data("labels.jsonl"), "utf8");
+
+const TXID = "f91d0a8a78462bc59398f2c5d7a84fcff491c26ba54c4833478b202796c8aafd";
+
+test("parses labels to plain objects", () => {
+  const labels = bip329.parseLabels(jsonl);
+
+  assert.equal(labels.length, 72);
+  assert.deepEqual(labels[0], {
+    type: "xpub",
+    ref: "xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj",
+    label: "BIP39",
+  });
+  assert.equal(labels.filter((label) => label.type === "tx").length, 21);
+});
+
+test("export loops back", () => {
+  const labels = bip329.parseLabels(jsonl);
+  const exported = bip329.exportLabels(labels);
+
+  assert.deepEqual(bip329.parseLabels(exported), labels);
+});
+
+test("reports the line of invalid records", () => {
+  const lines = jsonl.split("\n").slice(0, 2);
+  lines.push('{"type":"tx","ref":"not a txid"}');
+
+  assert.throws(() => bip329.parseLabels(lines.join("\n")), /line 3/);
+  assert.throws(() => bip329.exportLabels([{ type: "tx", ref: "not a txid" }]));
+});
+
+test("ignores unknown record types when asked", () => {
+  const unknown = `${jsonl}\n{"type":"unknown","ref":"unknown"}`;
+
+  assert.throws(() => bip329.parseLabels(unknown));
+  assert.equal(bip329.parseLabels(unknown, true).length, 72);
+});
+
+test("spendable accepts string booleans", () => {
+  const [output] = bip329.parseLabels(`{"type":"output","ref":"${TXID}:0","spendable":"false"}`);
+
+  assert.equal(output.spendable, false);
+  assert.match(bip329.exportLabels([output]), /"spendable":false/);
+});
+
+test("merge replaces labels by ref and appends new labels", () => {
+  const labels = bip329.parseLabels(jsonl);
+  const updated = { ...labels[1], label: "updated" };
+  const added = { type: "tx", ref: TXID, label: "added" };
+
+  const merged = bip329.mergeLabels(labels, [updated, added]);
+
+  assert.equal(merged.length, labels.length + 1);
+  assert.deepEqual(merged[1], updated);
+  assert.deepEqual(merged.at(-1), added);
+  assert.deepEqual(merged.slice(2, -1), labels.slice(2));
+});
+
+test("encrypts and decrypts with a passphrase", () => {
+  const labels = bip329.parseLabels(jsonl);
+  const armored = bip329.encryptLabels(labels, "passphrase", 10);
+
+  assert.match(armored, /^-----BEGIN AGE ENCRYPTED FILE-----/);
+  assert.deepEqual(bip329.decryptLabels(armored, "passphrase"), labels);
+  assert.throws(() => bip329.decryptLabels(armored, "wrong passphrase"), /Wrong passphrase/);
+});
+
+test("decrypts binary age files", () => {
+  const encrypted = new Uint8Array(readFileSync(data("encrypted_labels.age")));
+
+  assert.deepEqual(bip329.decryptLabels(encrypted, "passphrase"), bip329.parseLabels(jsonl));
+  assert.throws(() => bip329.decryptLabels(42, "passphrase"), /string or a Uint8Array/);
+});